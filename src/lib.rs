@@ -1,39 +1,758 @@
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read, Write};
+#[cfg(feature = "zip")]
+use std::io::Seek;
 use std::iter::FromIterator;
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, RawFd};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
-/// Map keys to values.
-/// For each key in the file, add a key to the map with the value of the key.
-macro_rules! map_keys {
-    ($item:expr, { $($pat:expr => $field:expr),+ }) => {{
+/// Generate a chainable setter for each field of `OsRelease`.
+macro_rules! setters {
+    ($($field:ident),+) => {
         $(
-            if $item.starts_with($pat) {
-                $field = parse_line($item, $pat.len()).into();
-                continue;
+            #[doc = concat!("Sets `", stringify!($field), "`.")]
+            pub fn $field<S: Into<String>>(mut self, value: S) -> Self {
+                self.os_release.$field = value.into();
+                self
             }
         )+
-    }};
+    };
 }
 
 fn is_enclosed_with(line: &str, pattern: char) -> bool {
-    line.starts_with(pattern) && line.ends_with(pattern)
+    line.len() >= 2 && line.starts_with(pattern) && line.ends_with(pattern)
+}
+
+/// Returns `true` if `value` starts with a quote but doesn't end with the same quote.
+fn has_unterminated_quote(value: &str) -> bool {
+    ['"', '\''].iter().any(|&quote| value.starts_with(quote) && (value.len() == 1 || !value.ends_with(quote)))
 }
 
-/// Parse a line of the form `<key> = <value>`
-/// The key is expected to be a single word or something like MY_KEY_NAME.
-/// The line is returned as a `&str`.
-fn parse_line(line: &str, skip: usize) -> &str {
-    let line = line[skip..].trim();
-    if is_enclosed_with(line, '"') || is_enclosed_with(line, '\'') {
-        &line[1..line.len() - 1]
+/// Parse the value of a line, removing its quotes.
+/// Whitespace around unquoted values is removed only if `trim_unquoted` is `true`.
+fn parse_value(value: &str, trim_unquoted: bool) -> &str {
+    let trimmed = value.trim();
+    if is_enclosed_with(trimmed, '"') || is_enclosed_with(trimmed, '\'') {
+        &trimmed[1..trimmed.len() - 1]
+    } else if trim_unquoted {
+        trimmed
     } else {
-        line
+        value
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq)]
+/// Decode the escapes of a double-quoted value, reversing `quote`: like in the shell,
+/// a backslash followed by `"`, `\\`, `$` or `` ` `` stands for that character, other backslashes are kept.
+/// If `numeric` is `true`, the `\xHH` and `\0NNN` escapes are decoded too, like `\x41` or `\0101` for `A`.
+/// Numeric escapes which aren't followed by enough hex or octal digits are left as is,
+/// and so are all of them if the decoded bytes aren't valid UTF-8.
+/// An escaped backslash is a single character, so `\\x41` is decoded to `\x41`.
+fn unescape(value: &str, numeric: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            if let Some(&escaped @ (b'"' | b'\\' | b'$' | b'`')) = bytes.get(i + 1) {
+                decoded.push(escaped);
+                i += 2;
+                continue;
+            }
+            if let Some((length, byte)) = numeric_escape(&bytes[i + 1..]).filter(|_| numeric) {
+                decoded.push(byte);
+                i += 1 + length;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| unescape(value, false))
+}
+
+/// Decode the escape at the start of `escape`, the text following a backslash:
+/// `x` and two hex digits, or `0` and one to three octal digits.
+/// Returns the length of the escape and the decoded byte.
+fn numeric_escape(escape: &[u8]) -> Option<(usize, u8)> {
+    let (radix, digits) = match escape.first()? {
+        b'x' => (16, escape.get(1..3).filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))?),
+        b'0' => {
+            let count = escape[1..].iter().take(3).take_while(|b| matches!(b, b'0'..=b'7')).count();
+            (8, &escape[1..1 + count])
+        }
+        _ => return None,
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    let byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, radix).ok()?;
+    Some((1 + digits.len(), byte))
+}
+
+/// Lowercase `value` and replace each run of non-alphanumeric characters with a `-`.
+/// For example, "openSUSE Leap 15.5" becomes "opensuse-leap-15-5".
+fn slugify(value: &str) -> String {
+    value
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Returns `true` if `key` follows the grammar of the specification: `[A-Z][A-Z0-9_]*`.
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Split a line of the form `<key>=<value>` into its key and its raw value, according to `options`.
+/// Whitespace around the key is removed, and so is an `export ` prefix if `allow_export` is set.
+/// Returns `None` for blank lines, comments, lines without `=`,
+/// and lines with an invalid key if `lenient_keys` isn't set.
+fn split_line<'a>(line: &'a str, options: &ParseOptions) -> Option<(&'a str, &'a str)> {
+    let mut line = line.trim_start();
+    if line.is_empty() || line.starts_with(options.comment_chars.as_slice()) {
+        return None;
+    }
+    if options.allow_export {
+        if let Some(rest) = line.strip_prefix("export ") {
+            line = rest.trim_start();
+        }
+    }
+
+    let (key, value) = line.split_once('=')?;
+    let key = key.trim_end();
+    if !options.lenient_keys && !is_valid_key(key) {
+        return None;
+    }
+    Some((key, value))
+}
+
+/// Decode the raw value of a line according to `options`, removing its quotes
+/// and decoding the escapes of double-quoted values, see `unescape`.
+/// Numeric escapes are only decoded with `ParseOptions::numeric_escapes`.
+fn decode_value(value: &str, options: &ParseOptions) -> String {
+    let trimmed = value.trim();
+    let quoted = is_enclosed_with(trimmed, '"') || is_enclosed_with(trimmed, '\'');
+    if options.raw_values {
+        if quoted || options.trim_unquoted { trimmed.to_owned() } else { value.to_owned() }
+    } else if is_enclosed_with(trimmed, '"') {
+        unescape(parse_value(value, options.trim_unquoted), options.numeric_escapes)
+    } else {
+        parse_value(value, options.trim_unquoted).to_owned()
+    }
+}
+
+/// Quote a value with double quotes, escaping the characters special to the shell.
+/// For example, `Arch "Linux"` becomes `"Arch \"Linux\""`.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Paths of the os-release files read by `OsRelease::new`, from highest to lowest precedence.
+pub const DEFAULT_PATHS: [&str; 3] = ["/etc/os-release", "/run/os-release", "/usr/lib/os-release"];
+
+/// Ids of Red Hat Enterprise Linux and its known clones.
+/// Used by `OsRelease::is_enterprise_linux`.
+const ENTERPRISE_LINUX_IDS: &[&str] = &[
+    "rhel",
+    "centos",
+    "almalinux",
+    "rocky",
+    "ol",
+    "eurolinux",
+    "circle",
+];
+
+/// Markers found in the `VARIANT_ID` of immutable/atomic variants.
+/// Used by `OsRelease::is_atomic`.
+const ATOMIC_VARIANT_MARKERS: &[&str] = &[
+    "atomic",
+    "coreos",
+    "iot",
+    "kinoite",
+    "microos",
+    "onyx",
+    "sericea",
+    "silverblue",
+];
+
+/// Ids of distributions which are always immutable/atomic.
+/// Used by `OsRelease::is_atomic`.
+const ATOMIC_IDS: &[&str] = &[
+    "bottlerocket",
+    "endless",
+    "flatcar",
+    "opensuse-aeon",
+    "opensuse-kalpa",
+    "opensuse-microos",
+    "talos",
+    "vanilla",
+];
+
+/// Markers of development or pre-release builds.
+/// Used by `OsRelease::is_prerelease`.
+const PRERELEASE_MARKERS: &[&str] = &[
+    "alpha",
+    "beta",
+    "prerelease",
+    "rawhide",
+    "rc",
+    "sid",
+    "testing",
+    "unstable",
+];
+
+/// Family of distributions by id, like `("ubuntu", "debian")`.
+/// Used by `OsRelease::family`, which also matches the ids of `ID_LIKE`.
+const DISTRIBUTION_FAMILIES: &[(&str, &str)] = &[
+    ("debian", "debian"),
+    ("ubuntu", "debian"),
+    ("fedora", "fedora"),
+    ("rhel", "fedora"),
+    ("centos", "fedora"),
+    ("suse", "suse"),
+    ("opensuse", "suse"),
+    ("sles", "suse"),
+    ("arch", "arch"),
+    ("alpine", "alpine"),
+    ("gentoo", "gentoo"),
+];
+
+/// Ids of rolling release distributions which may set a `VERSION_ID`.
+/// Used by `OsRelease::release_model`.
+const ROLLING_IDS: &[&str] = &[
+    "arch",
+    "gentoo",
+    "opensuse-tumbleweed",
+    "void",
+];
+
+/// Keywords of editions, checked in order by `OsRelease::guessed_edition`.
+const EDITION_KEYWORDS: &[(&str, &str)] = &[
+    ("server", "server"),
+    ("minimal", "minimal"),
+    ("core", "minimal"),
+    ("desktop", "desktop"),
+    ("workstation", "desktop"),
+    ("kde", "desktop"),
+    ("gnome", "desktop"),
+];
+
+/// Package format of distributions by id, like `("debian", "deb")`.
+/// Used by `OsRelease::package_format`, which also matches the ids of `ID_LIKE`.
+const PACKAGE_FORMATS: &[(&str, &str)] = &[
+    ("debian", "deb"),
+    ("ubuntu", "deb"),
+    ("fedora", "rpm"),
+    ("rhel", "rpm"),
+    ("centos", "rpm"),
+    ("suse", "rpm"),
+    ("opensuse", "rpm"),
+    ("sles", "rpm"),
+    ("mageia", "rpm"),
+    ("arch", "pkg.tar"),
+    ("alpine", "apk"),
+    ("void", "xbps"),
+    ("gentoo", "ebuild"),
+    ("nixos", "nix"),
+    ("slackware", "txz"),
+];
+
+/// Support status of a distribution, computed from `SUPPORT_END`.
+/// See `OsRelease::support_status`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SupportStatus {
+    /// Support ends in more than the given threshold.
+    Supported,
+    /// Support ends in the given number of days.
+    EndingSoon(u32),
+    /// Support ended the given number of days ago.
+    Ended(u32),
+    /// `SUPPORT_END` is missing or isn't a valid date.
+    Unknown,
+}
+
+/// Kind of build, detected from `BUILD_ID`.
+/// See `OsRelease::build_kind`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildKind {
+    /// `BUILD_ID` is "rolling".
+    Rolling,
+    /// `BUILD_ID` is an ISO date like "2024-01-15".
+    Dated(String),
+    /// `BUILD_ID` is a hexadecimal commit hash like "3f2a9c1".
+    Commit(String),
+    /// `BUILD_ID` is set to something else.
+    Other(String),
+    /// `BUILD_ID` is not set.
+    None,
+}
+
+/// Version fields of a distribution, bundled together.
+/// See `OsRelease::version_info`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VersionInfo {
+    /// The `VERSION` field, like "22.04.3 LTS (Jammy Jellyfish)".
+    pub version:          String,
+    /// The `VERSION_ID` field, like "22.04".
+    pub version_id:       String,
+    /// The `VERSION_CODENAME` field, like "jammy".
+    pub version_codename: String,
+    /// The `(major, minor, patch)` components of `VERSION_ID`, like `(22, 4, 0)`.
+    /// Missing components are `0`. `None` if `VERSION_ID` is empty or not numeric.
+    pub numeric:          Option<(u64, u64, u64)>,
+    /// The codename of the version: `VERSION_CODENAME` if set,
+    /// else the text between parentheses in `VERSION`, like "Jammy Jellyfish".
+    /// `None` if neither is available.
+    pub codename:         Option<String>,
+}
+
+/// Differences between the extras of two structs, each sorted by key.
+/// See `OsRelease::extra_diff`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtraDiff {
+    /// Extras only in the other struct, as `(key, value)`.
+    pub added:   Vec<(String, String)>,
+    /// Extras only in this struct, as `(key, value)`.
+    pub removed: Vec<(String, String)>,
+    /// Extras in both structs with different values, as `(key, old value, new value)`.
+    pub changed: Vec<(String, String, String)>,
+}
+
+/// Identity of a system: its os-release file and its machine id, usable as a cache key.
+/// See `OsRelease::system_identity`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SystemIdentity {
+    /// The parsed os-release file.
+    pub os_release: OsRelease,
+    /// The content of `/etc/machine-id`, trimmed. Empty if the file doesn't exist.
+    pub machine_id: String,
+}
+
+/// Release model of a distribution, see `OsRelease::release_model`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReleaseModel {
+    /// Continuously updated, without versions, like Arch Linux.
+    Rolling,
+    /// Numbered point releases, like Debian 12 or Rocky Linux 9.3.
+    Pointed,
+    /// Versions named after their release date, like Ubuntu 22.04.
+    DateBased,
+    /// The model can't be guessed.
+    Unknown,
+}
+
+/// SUSE-specific release information, see `OsRelease::suse_info`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SuseInfo {
+    /// The product, from the `CPE_NAME` extra like "sles" in "cpe:/o:suse:sles:15:sp5", else `id`.
+    pub product:      String,
+    /// The major version, like `15` for "15.5". `None` if `VERSION_ID` isn't numeric.
+    pub major:        Option<u64>,
+    /// The service pack, like `5` for "15-SP5". `None` if it can't be found.
+    pub service_pack: Option<u64>,
+}
+
+/// Location of an os-release file on disk, see `OsRelease::source_info`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceInfo {
+    /// The canonical path of the file, with every symlink resolved, like `/usr/lib/os-release`.
+    pub resolved_path: std::path::PathBuf,
+    /// Whether the path itself is a symlink.
+    pub is_symlink:    bool,
+    /// The target of the symlink as written in the link, like `../usr/lib/os-release`.
+    /// `None` if the path isn't a symlink.
+    pub target:        Option<std::path::PathBuf>,
+}
+
+/// Init system of the running system.
+/// See `OsRelease::init_system`. Only available on Linux, like the detection.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitSystem {
+    /// systemd, detected by `/run/systemd/system`.
+    Systemd,
+    /// OpenRC, detected by `/run/openrc`.
+    OpenRc,
+    /// SysVinit, detected by `/etc/inittab`.
+    SysVinit,
+    /// runit, detected by `/run/runit`.
+    Runit,
+    /// None of the above was detected.
+    Unknown,
+}
+
+/// Number of days between 1970-01-01 and the given date.
+/// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil((year, month, day): (i32, u8, u8)) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Number of days in `month` of `year`, taking leap years into account.
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Split a version like "22.04" into its numeric components.
+/// Returns `None` if the version is empty or a component isn't a number.
+fn version_components(version: &str) -> Option<Vec<u64>> {
+    if version.is_empty() {
+        return None;
+    }
+    version.split('.').map(|component| component.parse().ok()).collect()
+}
+
+/// Returns the architecture of the running system, named like in `ARCHITECTURE`, like "x86-64" or "arm64".
+/// Architectures unknown to systemd are named like in `std::env::consts::ARCH`.
+fn current_architecture() -> &'static str {
+    match std::env::consts::ARCH {
+        "powerpc64" if cfg!(target_endian = "little") => "ppc64-le",
+        arch => architecture_name(arch),
+    }
+}
+
+/// Returns the `ARCHITECTURE` name of a Rust or a common alternative architecture name,
+/// like "arm64" for "aarch64" or "x86-64" for "amd64".
+fn architecture_name(arch: &str) -> &str {
+    match arch {
+        "x86_64" | "amd64" => "x86-64",
+        "i386" | "i686" => "x86",
+        "aarch64" => "arm64",
+        "powerpc64" => "ppc64",
+        "powerpc" => "ppc",
+        "ppc64le" => "ppc64-le",
+        arch => arch,
+    }
+}
+
+/// Compare two versions component by component.
+/// Missing components are treated as `0`, so "23" is equal to "23.0".
+fn compare_versions(a: &[u64], b: &[u64]) -> Ordering {
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Default maximum number of passes of `OsRelease::expand_variables`.
+pub const DEFAULT_EXPANSION_LIMIT: usize = 16;
+
+/// Maximum length in bytes of a value expanded by `OsRelease::expand_variables`.
+const MAX_EXPANDED_LEN: usize = 4096;
+
+/// Replace every `${KEY}` in `value` with `lookup(KEY)`.
+/// References for which `lookup` returns `None` are left as-is.
+fn expand_references<'a, F: Fn(&str) -> Option<&'a str>>(value: &str, lookup: F) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        expanded.push_str(&rest[..start]);
+        match lookup(&rest[start + 2..end]) {
+            Some(replacement) => expanded.push_str(replacement),
+            None => expanded.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// How malformed values are handled by `OsRelease::parse_with_mode`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Malformed values are fixed when possible.
+    /// `OsRelease::parse_with_mode` returns a warning for each fixed value.
+    #[default]
+    Lenient,
+    /// Malformed values are left as-is and reported as an error.
+    Strict,
+}
+
+/// Standard field of `OsRelease`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OsReleaseField {
+    AnsiColor,
+    BuildId,
+    BugReportUrl,
+    DocumentationUrl,
+    HomeUrl,
+    Id,
+    IdLike,
+    ImageId,
+    Logo,
+    Name,
+    PrettyName,
+    PrivacyPolicyUrl,
+    VariantId,
+    Version,
+    VersionCodename,
+    VersionId,
+    SupportEnd,
+    SupportUrl,
+}
+
+impl OsReleaseField {
+    /// Every standard field.
+    pub const ALL: [OsReleaseField; 18] = [
+        OsReleaseField::AnsiColor,
+        OsReleaseField::BuildId,
+        OsReleaseField::BugReportUrl,
+        OsReleaseField::DocumentationUrl,
+        OsReleaseField::HomeUrl,
+        OsReleaseField::Id,
+        OsReleaseField::IdLike,
+        OsReleaseField::ImageId,
+        OsReleaseField::Logo,
+        OsReleaseField::Name,
+        OsReleaseField::PrettyName,
+        OsReleaseField::PrivacyPolicyUrl,
+        OsReleaseField::VariantId,
+        OsReleaseField::Version,
+        OsReleaseField::VersionCodename,
+        OsReleaseField::VersionId,
+        OsReleaseField::SupportEnd,
+        OsReleaseField::SupportUrl,
+    ];
+
+    /// Returns the key of the field in os-release files, like "VERSION_ID".
+    pub fn key(self) -> &'static str {
+        match self {
+            OsReleaseField::AnsiColor => "ANSI_COLOR",
+            OsReleaseField::BuildId => "BUILD_ID",
+            OsReleaseField::BugReportUrl => "BUG_REPORT_URL",
+            OsReleaseField::DocumentationUrl => "DOCUMENTATION_URL",
+            OsReleaseField::HomeUrl => "HOME_URL",
+            OsReleaseField::Id => "ID",
+            OsReleaseField::IdLike => "ID_LIKE",
+            OsReleaseField::ImageId => "IMAGE_ID",
+            OsReleaseField::Logo => "LOGO",
+            OsReleaseField::Name => "NAME",
+            OsReleaseField::PrettyName => "PRETTY_NAME",
+            OsReleaseField::PrivacyPolicyUrl => "PRIVACY_POLICY_URL",
+            OsReleaseField::VariantId => "VARIANT_ID",
+            OsReleaseField::Version => "VERSION",
+            OsReleaseField::VersionCodename => "VERSION_CODENAME",
+            OsReleaseField::VersionId => "VERSION_ID",
+            OsReleaseField::SupportEnd => "SUPPORT_END",
+            OsReleaseField::SupportUrl => "SUPPORT_URL",
+        }
+    }
+
+    /// Returns a short description of the field, like "The version id of the distribution."
+    pub fn description(self) -> &'static str {
+        match self {
+            OsReleaseField::AnsiColor => "ANSI color code for the distribution.",
+            OsReleaseField::BuildId => "Id of the build, \"rolling\" for a rolling release.",
+            OsReleaseField::BugReportUrl => "Url of bug reporting system.",
+            OsReleaseField::DocumentationUrl => "Url of the documentation for the distribution.",
+            OsReleaseField::HomeUrl => "Homepage of the distribution.",
+            OsReleaseField::Id => "The name of the distribution in the form of a codename.",
+            OsReleaseField::IdLike => "Related distribution id.",
+            OsReleaseField::ImageId => "Id of the image, for image-based distributions.",
+            OsReleaseField::Logo => "Logo of the distribution.",
+            OsReleaseField::Name => "The name of the operating system.",
+            OsReleaseField::PrettyName => "The pretty name of the operating system.",
+            OsReleaseField::PrivacyPolicyUrl => "Privacy policy url.",
+            OsReleaseField::VariantId => "The variant id of the distribution.",
+            OsReleaseField::Version => "The version of the distribution.",
+            OsReleaseField::VersionCodename => "The version codename of the distribution.",
+            OsReleaseField::VersionId => "The version id of the distribution.",
+            OsReleaseField::SupportEnd => "The date at which support for this version of the distribution ends.",
+            OsReleaseField::SupportUrl => "The support url of the distribution.",
+        }
+    }
+
+    /// Returns the field of `key`, like "VERSION_ID".
+    /// Returns `None` if `key` isn't a standard key.
+    pub fn from_key(key: &str) -> Option<OsReleaseField> {
+        OsReleaseField::ALL.iter().copied().find(|field| field.key() == key)
+    }
+}
+
+/// Options of `OsRelease::parse_with_options`.
+/// The default options are lenient, see the documentation of each field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// How malformed values are handled. Defaults to `ParseMode::Lenient`.
+    pub mode:                      ParseMode,
+    /// Remove whitespace around unquoted values. Defaults to `true`.
+    /// Disable it to preserve intentionally padded values.
+    pub trim_unquoted:             bool,
+    /// Remove a UTF-8 byte order mark at the start of the content. Defaults to `true`.
+    /// In strict mode, a byte order mark is an error instead, see `OsReleaseError::UnexpectedBom`.
+    pub strip_bom:                 bool,
+    /// Accept lines prefixed with `export`, like `export NAME="Arch Linux"`. Defaults to `true`.
+    pub allow_export:              bool,
+    /// Accept keys which don't follow the `[A-Z][A-Z0-9_]*` grammar of the specification,
+    /// like `foo` or `1FOO`. Defaults to `true`.
+    /// When disabled, lines with such keys are ignored.
+    pub lenient_keys:              bool,
+    /// Also store standard keys in `extra`, so it mirrors every key of the file. Defaults to `false`.
+    pub include_standard_in_extra: bool,
+    /// Characters starting a comment line, after leading whitespace. Defaults to `['#']`.
+    pub comment_chars:             Vec<char>,
+    /// Store values as written in the file, quotes and escapes included, like `"\"Arch Linux\""`.
+    /// Whitespace around values is still removed, according to `trim_unquoted` for unquoted values. Defaults to `false`.
+    pub raw_values:                bool,
+    /// Decode the `\xHH` and `\0NNN` escapes of double-quoted values, written by some generators,
+    /// like `\x41` or `\0101` for `A`. Defaults to `false`, like the shell, which keeps them as is.
+    pub numeric_escapes:           bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            mode:                      ParseMode::Lenient,
+            trim_unquoted:             true,
+            strip_bom:                 true,
+            allow_export:              true,
+            lenient_keys:              true,
+            include_standard_in_extra: false,
+            comment_chars:             vec!['#'],
+            raw_values:                false,
+            numeric_escapes:           false,
+        }
+    }
+}
+
+/// Encoding of the bytes given to `OsRelease::from_bytes_with_encoding`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, invalid sequences are replaced with `U+FFFD`.
+    Utf8Lossy,
+    /// ISO-8859-1, every byte is a character.
+    Latin1,
+    /// UTF-8, invalid sequences are an error.
+    Utf8Strict,
+}
+
+/// Options of `OsRelease::write_with_options`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Write a `PRETTY_NAME` built with `OsRelease::display_name` when `pretty_name` is empty.
+    /// The struct itself isn't modified. Defaults to `false`.
+    pub synthesize_pretty_name: bool,
+}
+
+/// Error returned when an os-release file can't be parsed.
+#[derive(Debug)]
+pub enum OsReleaseError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The `ID` value is not a valid id.
+    /// For example, `ID="arch linux"` contains a space.
+    InvalidId(String),
+    /// A value starts with a quote but doesn't end with it.
+    /// Contains the 1-based line number and the content of the line.
+    UnterminatedQuote { line: usize, content: String },
+    /// The content is not valid UTF-8.
+    InvalidUtf8(std::str::Utf8Error),
+    /// The content starts with a UTF-8 byte order mark, which is invalid in os-release files.
+    /// Only returned in strict mode, lenient mode strips it.
+    UnexpectedBom,
+    /// The content is not valid JSON.
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// The JSON pointer doesn't lead to an object.
+    #[cfg(feature = "json")]
+    JsonPointer(String),
+    /// The stream is not gzip-compressed.
+    #[cfg(feature = "gzip")]
+    NotGzip,
+    /// The stream is not a valid tar archive.
+    #[cfg(feature = "tar")]
+    NotTar(io::Error),
+    /// The archive contains no os-release file.
+    NoOsRelease,
+    /// The path is a symlink which leads back to itself, or whose chain is longer than 40 links.
+    /// Contains the path and the target of its symlink.
+    SymlinkLoop { path: std::path::PathBuf, target: std::path::PathBuf },
+    /// The path is a symlink whose final target doesn't exist.
+    /// Contains the path and the target of its symlink.
+    BrokenSymlink { path: std::path::PathBuf, target: std::path::PathBuf },
+}
+
+impl fmt::Display for OsReleaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OsReleaseError::Io(why) => write!(f, "{}", why),
+            OsReleaseError::InvalidId(id) => write!(f, "invalid ID {:?}: ids must not contain spaces", id),
+            OsReleaseError::UnterminatedQuote { line, content } => write!(f, "unterminated quote at line {}: {}", line, content),
+            OsReleaseError::InvalidUtf8(why) => write!(f, "invalid UTF-8: {}", why),
+            OsReleaseError::UnexpectedBom => write!(f, "unexpected byte order mark at the start of the file"),
+            #[cfg(feature = "json")]
+            OsReleaseError::Json(why) => write!(f, "invalid JSON: {}", why),
+            #[cfg(feature = "json")]
+            OsReleaseError::JsonPointer(pointer) => write!(f, "no JSON object at {:?}", pointer),
+            #[cfg(feature = "gzip")]
+            OsReleaseError::NotGzip => write!(f, "not a gzip stream"),
+            #[cfg(feature = "tar")]
+            OsReleaseError::NotTar(why) => write!(f, "not a tar archive: {}", why),
+            OsReleaseError::NoOsRelease => write!(f, "no os-release file in the archive"),
+            OsReleaseError::SymlinkLoop { path, target } => write!(f, "symlink loop at {:?} (pointing to {:?})", path, target),
+            OsReleaseError::BrokenSymlink { path, target } => write!(f, "broken symlink at {:?} (pointing to {:?})", path, target),
+        }
+    }
+}
+
+impl Error for OsReleaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            OsReleaseError::Io(why) => Some(why),
+            OsReleaseError::InvalidUtf8(why) => Some(why),
+            #[cfg(feature = "json")]
+            OsReleaseError::Json(why) => Some(why),
+            #[cfg(feature = "tar")]
+            OsReleaseError::NotTar(why) => Some(why),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for OsReleaseError {
+    fn from(why: io::Error) -> Self {
+        OsReleaseError::Io(why)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OsRelease {
     /// ANSI color code for the distribution.
     /// This is a six numbers.
@@ -64,6 +783,10 @@ pub struct OsRelease {
     /// If the distro is derived from another distro, it will be the id of the parent distro.
     /// For example, on Manjaro, this is "arch".
     pub id_like:            String,
+    /// Id of the image, for image-based distributions.
+    /// This is a lower-case string identifying the image, not the distribution.
+    /// For example, on an image built with mkosi, this could be "vendorx-cashier-system".
+    pub image_id:           String,
     /// The name of the operating system.
     /// This is the name of the operating system as it appears to the user.
     /// For example, on ArchLinux, this is "Arch Linux".
@@ -78,6 +801,10 @@ pub struct OsRelease {
     /// This is the URL of the privacy policy of the distribution.
     /// For example, on ArchLinux, this is "https://www.archlinux.org/legal/privacy-policy/".
     pub privacy_policy_url: String,
+    /// The variant id of the distribution.
+    /// This is a lower-case string identifying a specific variant or edition of the distribution.
+    /// For example, on Fedora Silverblue, this is "silverblue".
+    pub variant_id:         String,
     /// The version of the distribution.
     /// This is the version of the distribution.
     /// For example, on ArchLinux, this is "" because ArchLinux is rolling release so ArchLinux doesn't have version.
@@ -90,6 +817,10 @@ pub struct OsRelease {
     /// This is the version id of the distribution.
     /// For example, on ArchLinux, this is "" because ArchLinux is rolling release so ArchLinux doesn't have version.
     pub version_id:         String,
+    /// The date at which support for this version of the distribution ends.
+    /// This is a date in the `YYYY-MM-DD` format.
+    /// For example, on Fedora 39, this is "2024-11-12".
+    pub support_end:        String,
     /// The support url of the distribution.
     /// This is the support url of the distribution.
     /// For example, on ArchLinux, this is "https://bbs.archlinux.org/"
@@ -97,111 +828,3703 @@ pub struct OsRelease {
 }
 
 impl OsRelease {
-    /// Reads the `/etc/os-release` file and returns a `OsRelease` struct.
-    /// If `/etc/os-release` does not exist, searches for `/usr/lib/os-release`
+    /// Reads the os-release files of `DEFAULT_PATHS` and returns a `OsRelease` struct.
+    /// Values of `/etc/os-release` override values of `/run/os-release`,
+    /// which override values of `/usr/lib/os-release`.
+    /// Missing files are skipped, an error is returned if none of them exist or one of them can't be read.
     pub fn new() -> io::Result<OsRelease> {
-        let file = BufReader::new(open("/etc/os-release").unwrap_or(open("/usr/lib/os-release")?));
-        Ok(OsRelease::from_iter(file.lines().flat_map(|line| line)))
+        OsRelease::new_from_layers(&DEFAULT_PATHS)
+    }
+
+    /// Reads and merges os-release files given from highest to lowest precedence.
+    /// Missing files are skipped, an error is returned if none of them exist,
+    /// or if one of them can't be read. Lines which aren't valid UTF-8 are skipped.
+    pub fn new_from_layers<P: AsRef<Path>>(paths: &[P]) -> io::Result<OsRelease> {
+        let mut layers = Vec::new();
+        let mut error = None;
+        for path in paths {
+            match open(path) {
+                Ok(file) => layers.push(read_lines(BufReader::new(file))?.join("\n")),
+                Err(why) if why.kind() == io::ErrorKind::NotFound => error = Some(why),
+                Err(why) => return Err(why),
+            }
+        }
+
+        match error {
+            Some(why) if layers.is_empty() => Err(why),
+            _ => Ok(OsRelease::from_layers(layers)),
+        }
+    }
+
+    /// Merges the contents of os-release files given from highest to lowest precedence.
+    /// A non-empty value of a layer overrides the values of the same key in the layers after it.
+    /// Each layer is parsed on its own, so a byte order mark is stripped from the start of every layer.
+    pub fn from_layers<I, S>(layers: I) -> OsRelease
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut os_release = OsRelease::default();
+        for layer in layers {
+            os_release.fill_defaults(&OsRelease::from_iter(layer.as_ref().lines().map(String::from)));
+        }
+        os_release
+    }
+
+    /// Returns where `/etc/os-release` comes from: its resolved path and its symlink target, if any.
+    /// On most systems, it is a symlink to `../usr/lib/os-release`.
+    pub fn source_info() -> io::Result<SourceInfo> {
+        OsRelease::source_info_at("/etc/os-release")
+    }
+
+    /// Returns where the os-release file at `path` comes from. See `OsRelease::source_info`.
+    /// Returns an error if `path` or the file it points to doesn't exist.
+    pub fn source_info_at<P: AsRef<Path>>(path: P) -> io::Result<SourceInfo> {
+        let path = path.as_ref();
+        let is_symlink = std::fs::symlink_metadata(path)?.file_type().is_symlink();
+        Ok(SourceInfo {
+            resolved_path: std::fs::canonicalize(path)?,
+            is_symlink,
+            target: if is_symlink { Some(std::fs::read_link(path)?) } else { None },
+        })
+    }
+
+    /// Reads the os-release file of the initrd, at `/run/initramfs/etc/os-release`,
+    /// falling back to `/run/initramfs/usr/lib/os-release` if it doesn't exist. See `OsRelease::new_from_root`.
+    /// Returns an error of kind `NotFound` if the initrd has no os-release file.
+    pub fn new_initrd() -> io::Result<OsRelease> {
+        OsRelease::new_from_root("/run/initramfs")
+    }
+
+    /// Reads the os-release file of the host from inside a flatpak sandbox, at `/run/host/os-release`,
+    /// falling back to the os-release files of the sandbox read by `OsRelease::new` outside of flatpak.
+    pub fn new_flatpak_host() -> io::Result<OsRelease> {
+        OsRelease::new_flatpak_host_at("/run/host", &DEFAULT_PATHS)
+    }
+
+    /// Reads the `os-release` file of `host_dir`, like `/run/host` in a flatpak sandbox,
+    /// falling back to the os-release files at `fallback_paths` if it doesn't exist.
+    /// See `OsRelease::new_from_layers` for the fallback.
+    pub fn new_flatpak_host_at<P: AsRef<Path>, Q: AsRef<Path>>(host_dir: P, fallback_paths: &[Q]) -> io::Result<OsRelease> {
+        match OsRelease::new_from(host_dir.as_ref().join("os-release")) {
+            Err(why) if why.kind() == io::ErrorKind::NotFound => OsRelease::new_from_layers(fallback_paths),
+            result => result,
+        }
+    }
+
+    /// Reads the os-release file of the system mounted at `root`, at `etc/os-release`,
+    /// falling back to `usr/lib/os-release` if it doesn't exist.
+    /// As in the specification, only the first existing file is read, the two files aren't merged.
+    /// Returns an error of kind `NotFound` if neither of them exist.
+    pub fn new_from_root<P: AsRef<Path>>(root: P) -> io::Result<OsRelease> {
+        let root = root.as_ref();
+        match OsRelease::new_from(root.join("etc/os-release")) {
+            Err(why) if why.kind() == io::ErrorKind::NotFound => OsRelease::new_from(root.join("usr/lib/os-release")),
+            result => result,
+        }
     }
 
     /// Attempt to parse any `/etc/os-release`-like file.
     pub fn new_from<P: AsRef<Path>>(path: P) -> io::Result<OsRelease> {
-        let file = BufReader::new(open(&path)?);
-        Ok(OsRelease::from_iter(file.lines().flat_map(|line| line)))
+        OsRelease::from_reader(open(&path)?)
     }
-}
 
-impl FromIterator<String> for OsRelease {
-    /// Parse the lines of the `/etc/os-release` file.
-    /// The lines are expected to be in the form of `<key> = <value>`.
-    /// If keys aren't in the list of standard keys, there will be in `extra` field.
-    /// See the `OsRelease` struct for the list of standard keys.
-    fn from_iter<I: IntoIterator<Item = String>>(lines: I) -> Self {
-        let mut os_release = Self::default();
+    /// Parse an `/etc/os-release`-like file like `OsRelease::new_from`, with clear errors for broken symlinks,
+    /// as found in untrusted images: `OsReleaseError::SymlinkLoop` if the symlink chain loops,
+    /// and `OsReleaseError::BrokenSymlink` if its final target doesn't exist.
+    pub fn new_from_guarded<P: AsRef<Path>>(path: P) -> Result<OsRelease, OsReleaseError> {
+        let path = path.as_ref();
+        match File::open(path) {
+            Ok(file) => Ok(OsRelease::from_reader(file)?),
+            Err(why) => Err(symlink_error(path).unwrap_or(OsReleaseError::Io(why))),
+        }
+    }
 
-        for line in lines {
-            let line = line.trim();
-            map_keys!(line, {
-                "ANSI_COLOR=" => os_release.ansi_color,
-                "BUILD_ID=" => os_release.build_id,
-                "BUG_REPORT_URL=" => os_release.bug_report_url,
-                "DOCUMENTATION_URL=" => os_release.documentation_url,
-                "HOME_URL=" => os_release.home_url,
-                "ID=" => os_release.id,
-                "ID_LIKE=" => os_release.id_like,
-                "LOGO=" => os_release.logo,
-                "NAME=" => os_release.name,
-                "PRETTY_NAME=" => os_release.pretty_name,
-                "PRIVACY_POLICY_URL=" => os_release.privacy_policy_url,
-                "SUPPORT_URL=" => os_release.support_url,
-                "VERSION=" => os_release.version,
-                "VERSION_ID=" => os_release.version_id,
-                "VERSION_CODENAME=" => os_release.version_codename
-            });
+    /// Parse an `/etc/os-release`-like file from a reader, like a decompressor or a socket.
+    /// Lines which aren't valid UTF-8 are skipped; errors while reading are returned.
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<OsRelease> {
+        Ok(OsRelease::from_iter(read_lines(BufReader::new(reader))?))
+    }
+
+    /// Parse an `/etc/os-release`-like file which may be gzip-compressed.
+    /// The file is decompressed if it starts with the gzip magic bytes (`1f 8b`), else it is parsed as is.
+    #[cfg(feature = "gzip")]
+    pub fn new_from_auto<P: AsRef<Path>>(path: P) -> io::Result<OsRelease> {
+        let mut file = BufReader::new(open(&path)?);
+        if file.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+            OsRelease::from_reader(flate2::bufread::GzDecoder::new(file))
+        } else {
+            OsRelease::from_reader(file)
+        }
+    }
+
+    /// Parse a zstd-compressed `/etc/os-release`-like file, like `os-release.zst`.
+    #[cfg(feature = "zstd")]
+    pub fn new_from_zst<P: AsRef<Path>>(path: P) -> io::Result<OsRelease> {
+        OsRelease::from_reader(zstd::Decoder::new(open(&path)?)?)
+    }
+
+    /// Parse an already open `/etc/os-release`-like file from its file descriptor.
+    /// The file descriptor is consumed: it is closed once the file has been read,
+    /// even if reading fails. Like `OsRelease::from_reader`, lines which aren't valid UTF-8
+    /// are skipped and errors while reading are returned.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open file descriptor owned by the caller, and must not be used
+    /// or closed by the caller after this call.
+    #[cfg(unix)]
+    pub unsafe fn from_raw_fd(fd: RawFd) -> io::Result<OsRelease> {
+        OsRelease::from_reader(File::from_raw_fd(fd))
+    }
+
+    /// Sets the value of `key`, like "PRETTY_NAME", in the os-release file at `path`, in place.
+    /// Only the lines of `key` are rewritten, keeping their indentation, `export` prefix and quoting style;
+    /// every other line, including comments and ordering, is left byte-identical.
+    /// If `key` is absent, a `KEY="value"` line is appended.
+    /// The file is written atomically: a uniquely named temporary file is written and synced next to it,
+    /// then renamed over it. The temporary file is removed if any step fails.
+    /// If `path` is a symlink, its target is edited.
+    pub fn edit_field_in_file<P: AsRef<Path>>(path: P, key: &str, new_value: &str) -> io::Result<()> {
+        let path = std::fs::canonicalize(path)?;
+        let content = std::fs::read_to_string(&path)?;
+
+        let options = ParseOptions::default();
+        let mut edited = String::with_capacity(content.len());
+        let mut found = false;
+        for line in content.split_inclusive('\n') {
+            let body = line.trim_end_matches(&['\r', '\n'][..]);
+            match split_line(body, &options) {
+                Some((line_key, value)) if line_key == key => {
+                    let before_value = body[..body.len() - value.len() - 1].trim_end();
+                    let prefix = &before_value[..before_value.len() - line_key.len()];
+                    let value = value.trim();
+                    let quoted = if is_enclosed_with(value, '\'') && !new_value.contains('\'') {
+                        format!("'{}'", new_value)
+                    } else if is_enclosed_with(value, '"') {
+                        quote(new_value)
+                    } else if new_value.chars().all(|c| c.is_ascii_alphanumeric() || "._-:/+,".contains(c)) {
+                        new_value.to_owned()
+                    } else {
+                        quote(new_value)
+                    };
+                    edited.push_str(&format!("{}{}={}{}", prefix, key, quoted, &line[body.len()..]));
+                    found = true;
+                }
+                _ => edited.push_str(line),
+            }
+        }
+        if !found {
+            if !edited.is_empty() && !edited.ends_with('\n') {
+                edited.push('\n');
+            }
+            edited.push_str(&format!("{}={}\n", key, quote(new_value)));
+        }
+
+        static EDITS: AtomicUsize = AtomicUsize::new(0);
+        let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let temporary = path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            file_name,
+            std::process::id(),
+            EDITS.fetch_add(1, AtomicOrdering::Relaxed),
+        ));
+        let write = || -> io::Result<()> {
+            let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&temporary)?;
+            file.write_all(edited.as_bytes())?;
+            file.set_permissions(std::fs::metadata(&path)?.permissions())?;
+            file.sync_all()?;
+            std::fs::rename(&temporary, &path)
+        };
+        write().inspect_err(|_| { let _ = std::fs::remove_file(&temporary); })
+    }
+
+    /// Attempt to parse any `/etc/os-release`-like file, giving up after `timeout`.
+    /// The file is read on a newly spawned thread, so a read hanging on a slow or broken
+    /// filesystem doesn't block the caller. If the read doesn't complete in time,
+    /// an error of kind `TimedOut` is returned and the thread is left running in the background.
+    pub fn new_from_timeout<P: AsRef<Path>>(path: P, timeout: Duration) -> io::Result<OsRelease> {
+        let path = path.as_ref().to_owned();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(OsRelease::new_from(path));
+        });
+
+        receiver.recv_timeout(timeout).unwrap_or_else(|_| Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("reading os-release file timed out after {:?}", timeout)
+        )))
+    }
+
+    /// Returns the identity of the running system: the os-release files read by `OsRelease::new`
+    /// and the machine id of `/etc/machine-id`.
+    pub fn system_identity() -> io::Result<SystemIdentity> {
+        OsRelease::system_identity_at(&DEFAULT_PATHS, "/etc/machine-id")
+    }
+
+    /// Returns the identity of a system from its os-release files, from highest to lowest precedence,
+    /// and from its machine id file. See `OsRelease::new_from_layers`.
+    /// The machine id is empty if `machine_id_path` doesn't exist.
+    pub fn system_identity_at<P: AsRef<Path>, Q: AsRef<Path>>(
+        os_release_paths: &[P],
+        machine_id_path: Q,
+    ) -> io::Result<SystemIdentity> {
+        let os_release = OsRelease::new_from_layers(os_release_paths)?;
+        let machine_id = match std::fs::read_to_string(machine_id_path) {
+            Ok(machine_id) => machine_id.trim().to_owned(),
+            Err(why) if why.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(why) => return Err(why),
+        };
+        Ok(SystemIdentity { os_release, machine_id })
+    }
+
+    /// Detects the init system of the running system.
+    /// This isn't read from the os-release file, see `OsRelease::init_system_at` for the detection.
+    #[cfg(target_os = "linux")]
+    pub fn init_system() -> InitSystem {
+        OsRelease::init_system_at("/")
+    }
+
+    /// Detects the init system of the system mounted at `root`, by probing in this order:
+    /// - `run/systemd/system` for systemd.
+    /// - `run/openrc` for OpenRC.
+    /// - `run/runit` for runit.
+    /// - `etc/inittab` for SysVinit.
+    #[cfg(target_os = "linux")]
+    pub fn init_system_at<P: AsRef<Path>>(root: P) -> InitSystem {
+        let root = root.as_ref();
+        if root.join("run/systemd/system").is_dir() {
+            InitSystem::Systemd
+        } else if root.join("run/openrc").exists() {
+            InitSystem::OpenRc
+        } else if root.join("run/runit").exists() {
+            InitSystem::Runit
+        } else if root.join("etc/inittab").exists() {
+            InitSystem::SysVinit
+        } else {
+            InitSystem::Unknown
+        }
+    }
+
+    /// Parse the content of an os-release file, handling malformed values according to `mode`.
+    /// In lenient mode, malformed values are fixed and a warning is returned for each of them:
+    /// spaces in `ID` are replaced with `-`, so `ID="arch linux"` becomes `arch-linux`.
+    /// In strict mode, the first malformed value is returned as an error,
+    /// and values with an unterminated quote like `NAME="Foo` are rejected.
+    /// The other options are the defaults of `ParseOptions`.
+    pub fn parse_with_mode(content: &str, mode: ParseMode) -> Result<(OsRelease, Vec<String>), OsReleaseError> {
+        OsRelease::parse_with_warnings(content, &ParseOptions { mode, ..ParseOptions::default() })
+    }
 
-            if let Some(pos) = line.find('=') {
-                if line.len() > pos+1 {
-                    os_release.extra.insert(line[..pos].to_owned(), line[pos+1..].to_owned());
+    /// Parse the content of an os-release file according to `options`.
+    /// See `ParseOptions` for the available options.
+    pub fn parse_with_options(content: &str, options: &ParseOptions) -> Result<OsRelease, OsReleaseError> {
+        OsRelease::parse_with_warnings(content, options).map(|(os_release, _)| os_release)
+    }
+
+    /// Parse the content of an os-release file according to `options`,
+    /// returning the warnings of lenient mode along with the parsed struct.
+    fn parse_with_warnings(content: &str, options: &ParseOptions) -> Result<(OsRelease, Vec<String>), OsReleaseError> {
+        let mut os_release = OsRelease::default();
+        let mut warnings = Vec::new();
+        os_release.parse_lines(content.lines(), options, |_, _| ())?;
+
+        if os_release.id.contains(' ') {
+            match options.mode {
+                ParseMode::Strict => return Err(OsReleaseError::InvalidId(os_release.id)),
+                ParseMode::Lenient => {
+                    let id = os_release.id.replace(' ', "-");
+                    warnings.push(format!("replaced spaces in ID {:?} with {:?}", os_release.id, id));
+                    os_release.id = id;
                 }
             }
         }
 
+        Ok((os_release, warnings))
+    }
+
+    /// Parse the content of an os-release file given as bytes, decoded with `encoding`.
+    /// Returns `OsReleaseError::InvalidUtf8` if `encoding` is `Utf8Strict` and the bytes aren't valid UTF-8.
+    pub fn from_bytes_with_encoding(data: &[u8], encoding: Encoding) -> Result<OsRelease, OsReleaseError> {
+        let content = match encoding {
+            Encoding::Utf8Lossy => String::from_utf8_lossy(data).into_owned(),
+            Encoding::Latin1 => data.iter().map(|&b| char::from(b)).collect(),
+            Encoding::Utf8Strict => std::str::from_utf8(data).map_err(OsReleaseError::InvalidUtf8)?.to_owned(),
+        };
+        Ok(OsRelease::from_iter(content.lines().map(String::from)))
+    }
+
+    /// Builds a struct from the D-Bus properties of `systemd-hostnamed`, like `OperatingSystemPrettyName`.
+    /// These properties are mapped:
+    /// - `OperatingSystemReleaseData`, the os-release lines joined with newlines, is parsed first.
+    /// - `OperatingSystemPrettyName` to `pretty_name`.
+    /// - `OperatingSystemHomeURL` to `home_url`.
+    /// - `OperatingSystemCPEName` to the `CPE_NAME` extra.
+    ///
+    /// Other properties are ignored.
+    pub fn from_property_map(props: &BTreeMap<String, String>) -> OsRelease {
+        let mut os_release = match props.get("OperatingSystemReleaseData") {
+            Some(data) => OsRelease::from_iter(data.lines().map(String::from)),
+            None => OsRelease::default(),
+        };
+
+        for (property, key) in &[
+            ("OperatingSystemPrettyName", "PRETTY_NAME"),
+            ("OperatingSystemHomeURL", "HOME_URL"),
+            ("OperatingSystemCPEName", "CPE_NAME"),
+        ] {
+            if let Some(value) = props.get(*property) {
+                os_release.insert_value(key, value);
+            }
+        }
         os_release
     }
-}
 
-/// Open the file at the given path.
-/// If the file does not exist, return an error.
-fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
-    File::open(&path).map_err(|why| io::Error::new(
-        io::ErrorKind::Other,
-        format!("unable to open file at {:?}: {}", path.as_ref(), why)
-    ))
-}
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Returns, for each key of `content` in order, whether its value is quoted.
+    /// Unquoted values are fine when they contain no special characters, like `ID=arch`,
+    /// but unquoted values with spaces violate the specification.
+    pub fn quoting_report(content: &str) -> Vec<(String, bool)> {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| {
+                let value = value.trim();
+                let quoted = is_enclosed_with(value, '"') || is_enclosed_with(value, '\'');
+                (key.trim().to_owned(), quoted)
+            })
+            .collect()
+    }
 
-    const EXAMPLE: &str = r#"NAME="Arch Linux"
-PRETTY_NAME="Arch Linux"
-ID=arch
-BUILD_ID=rolling
-ANSI_COLOR="38;2;23;147;209"
-HOME_URL="https://archlinux.org/"
-DOCUMENTATION_URL="https://wiki.archlinux.org/"
-SUPPORT_URL="https://archlinux.org/"
-BUG_REPORT_URL="https://bugs.archlinux.org/"
-LOGO=archlinux-logo
-EXTRA_KEY=thing"#;
+    /// Parse the content of an os-release file and also return its original lines.
+    /// This allows mapping the parsed values back to the raw file, for example in an editor.
+    /// Lines are split on `\n` only, so `\r` and a trailing empty line are kept,
+    /// and joining the lines with `\n` gives back `content` byte for byte.
+    pub fn parse_with_source(content: &str) -> (OsRelease, Vec<String>) {
+        let lines: Vec<String> = content.split('\n').map(String::from).collect();
+        (OsRelease::from_iter(lines.iter().cloned()), lines)
+    }
 
-    #[test]
-    fn os_release() {
-        let os_release = OsRelease::from_iter(EXAMPLE.lines().map(|x| x.into()));
+    /// Parse the content of an os-release file and also return the line of each key.
+    /// Keys are mapped to the 1-based line number of the value stored in the struct,
+    /// so lines ignored by `FromIterator`, like `FOO=` with an empty value, have no span.
+    /// If a key appears several times, its last stored line is kept, like its value.
+    pub fn parse_with_spans(content: &str) -> (OsRelease, BTreeMap<String, usize>) {
+        let mut os_release = OsRelease::default();
+        let mut spans = BTreeMap::new();
+        os_release
+            .parse_lines(content.lines(), &ParseOptions::default(), |key, line| { spans.insert(key.to_owned(), line); })
+            .expect("lenient parsing can't fail");
 
-        assert_eq!(
-            os_release,
-            OsRelease {
-                name:               "Arch Linux".into(),
-                pretty_name:        "Arch Linux".into(),
-                version:            "".into(),
-                id:                 "arch".into(),
-                id_like:            "".into(),
-                version_id:         "".into(),
-                home_url:           "https://archlinux.org/".into(),
-                support_url:        "https://archlinux.org/".into(),
-                bug_report_url:     "https://bugs.archlinux.org/".into(),
-                privacy_policy_url: "".into(),
-                version_codename:   "".into(),
-                logo:               "archlinux-logo".into(),
-                build_id:           "rolling".into(),
-                ansi_color:         "38;2;23;147;209".into(),
-                documentation_url:   "https://wiki.archlinux.org/".into(),
-                extra: {
-                    let mut map = BTreeMap::new();
-                    map.insert("EXTRA_KEY".to_owned(), "thing".to_owned());
-                    map
+        (os_release, spans)
+    }
+
+    /// Returns `true` if both structs describe the same operating system.
+    /// Only `id`, `id_like`, `version_id`, `build_id`, `variant_id` and `image_id` are compared,
+    /// so cosmetic differences like URLs, names or the logo are ignored.
+    pub fn eq_identity(&self, other: &OsRelease) -> bool {
+        self.id == other.id
+            && self.id_like == other.id_like
+            && self.version_id == other.version_id
+            && self.build_id == other.build_id
+            && self.variant_id == other.variant_id
+            && self.image_id == other.image_id
+    }
+
+    /// Returns `true` if the operating system is immutable/atomic.
+    /// This is a heuristic, it returns `true` when one of these is true:
+    /// - `image_id` is set, since only image-based systems set it.
+    /// - `variant_id` contains one of the markers in `ATOMIC_VARIANT_MARKERS`,
+    ///   like "silverblue" or "coreos".
+    /// - `id` is one of the always-atomic distributions in `ATOMIC_IDS`,
+    ///   like "opensuse-microos" or "flatcar".
+    pub fn is_atomic(&self) -> bool {
+        !self.image_id.is_empty()
+            || ATOMIC_VARIANT_MARKERS.iter().any(|marker| self.variant_id.contains(marker))
+            || ATOMIC_IDS.contains(&self.id.as_str())
+    }
+
+    /// Parse `lines` according to `options` and store their values, see `OsRelease::insert_raw_value`.
+    /// `stored` is called with the key and the 1-based line number of each stored value.
+    /// In strict mode, a byte order mark or a value with an unterminated quote is an error.
+    fn parse_lines<I, S, F>(&mut self, lines: I, options: &ParseOptions, mut stored: F) -> Result<(), OsReleaseError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+        F: FnMut(&str, usize),
+    {
+        for (number, line) in lines.into_iter().enumerate() {
+            let line = line.as_ref();
+            let mut content = line;
+            if number == 0 && content.starts_with('\u{feff}') {
+                if options.mode == ParseMode::Strict {
+                    return Err(OsReleaseError::UnexpectedBom);
+                }
+                if options.strip_bom {
+                    content = &content['\u{feff}'.len_utf8()..];
                 }
             }
-        )
+
+            let (key, value) = match split_line(content, options) {
+                Some(pair) => pair,
+                None => continue,
+            };
+            if options.mode == ParseMode::Strict && has_unterminated_quote(value.trim()) {
+                return Err(OsReleaseError::UnterminatedQuote { line: number + 1, content: line.to_owned() });
+            }
+            if self.insert_raw_value(key, value, options) {
+                stored(key, number + 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode the raw value of a line according to `options` and store it with `OsRelease::insert_value`.
+    /// With `include_standard_in_extra`, non-empty values of standard keys are also stored in `extra`.
+    /// Returns `true` if the value was stored.
+    fn insert_raw_value(&mut self, key: &str, value: &str, options: &ParseOptions) -> bool {
+        let value = decode_value(value, options);
+        if options.include_standard_in_extra && !value.is_empty() {
+            self.extra.insert(key.to_owned(), value.clone());
+        }
+        self.insert_value(key, &value)
+    }
+
+    /// Store `value` in the standard field of `key`.
+    /// If `key` isn't a standard key, the value is stored in `extra` unless it is empty.
+    /// Returns `true` if the value was stored.
+    fn insert_value(&mut self, key: &str, value: &str) -> bool {
+        match self.field_mut(key) {
+            Some(field) => *field = value.to_owned(),
+            None if !value.is_empty() => { self.extra.insert(key.to_owned(), value.to_owned()); }
+            None => return false,
+        }
+        true
+    }
+
+    /// Parse additional lines into this struct, like `FromIterator` does for a new struct.
+    /// Values of the new lines override the current values of the same keys.
+    pub fn update_from_lines<I: IntoIterator<Item = String>>(&mut self, lines: I) {
+        self.parse_lines(lines, &ParseOptions::default(), |_, _| ()).expect("lenient parsing can't fail");
+    }
+
+    /// Builds a struct from already split `(key, value)` pairs, like the rows of a database.
+    /// Values are unquoted and routed like the lines of a file,
+    /// so `("NAME", "\"Arch Linux\"")` populates `name` and unknown keys are stored in `extra`.
+    pub fn from_pairs<I: IntoIterator<Item = (String, String)>>(pairs: I) -> OsRelease {
+        let mut os_release = OsRelease::default();
+        let options = ParseOptions::default();
+
+        for (key, value) in pairs {
+            os_release.insert_raw_value(key.trim(), &value, &options);
+        }
+
+        os_release
+    }
+
+    /// Parse the content of an os-release file like `FromIterator`, keeping only the keys accepted by `key_filter`.
+    /// Other lines are discarded without being stored, neither in the standard fields nor in `extra`.
+    /// For example, `|key| key.starts_with("VERSION")` keeps `VERSION`, `VERSION_ID` and `VERSION_CODENAME`.
+    /// `key_filter` is given the key as it is routed, without surrounding whitespace or `export` prefix.
+    pub fn parse_filtered<F: Fn(&str) -> bool>(content: &str, key_filter: F) -> OsRelease {
+        let mut os_release = OsRelease::default();
+        let options = ParseOptions::default();
+
+        for line in content.lines() {
+            match split_line(line, &options) {
+                Some((key, value)) if key_filter(key) => { os_release.insert_raw_value(key, value, &options); }
+                _ => (),
+            }
+        }
+
+        os_release
+    }
+
+    /// Parse lines whose keys are namespaced with `prefix`, like `GUEST_ID=arch`.
+    /// The prefix is stripped before routing the key, so `GUEST_ID=arch` populates `id`.
+    /// Lines whose key doesn't start with `prefix` are stored in `extra` with their original key.
+    pub fn from_iter_prefixed<I: IntoIterator<Item = String>>(lines: I, prefix: &str) -> OsRelease {
+        let mut os_release = OsRelease::default();
+        let options = ParseOptions::default();
+
+        for line in lines {
+            let (key, value) = match split_line(&line, &options) {
+                Some(pair) => pair,
+                None => continue,
+            };
+            match key.strip_prefix(prefix) {
+                Some(key) => { os_release.insert_raw_value(key, value, &options); }
+                None => {
+                    let value = decode_value(value, &options);
+                    if !value.is_empty() {
+                        os_release.extra.insert(key.to_owned(), value);
+                    }
+                }
+            }
+        }
+
+        os_release
+    }
+
+    /// Parse lines like `FromIterator`, but with case-insensitive keys.
+    /// Keys are uppercased before being routed, so `FOO=1` followed by `foo=2` results in a single `FOO=2` extra,
+    /// and `name=Foo` populates `name`.
+    pub fn from_iter_ci_extras<I: IntoIterator<Item = String>>(lines: I) -> OsRelease {
+        let mut os_release = OsRelease::default();
+        let options = ParseOptions::default();
+
+        for line in lines {
+            if let Some((key, value)) = split_line(&line, &options) {
+                os_release.insert_raw_value(&key.to_uppercase(), value, &options);
+            }
+        }
+
+        os_release
+    }
+
+    /// Returns the key and value of every standard field.
+    fn fields(&self) -> [(&'static str, &str); 18] {
+        OsReleaseField::ALL.map(|field| (field.key(), self.field(field)))
+    }
+
+    /// Returns a mutable reference to the standard field of `key`, like "VERSION_ID".
+    fn field_mut(&mut self, key: &str) -> Option<&mut String> {
+        let value = match OsReleaseField::from_key(key)? {
+            OsReleaseField::AnsiColor => &mut self.ansi_color,
+            OsReleaseField::BuildId => &mut self.build_id,
+            OsReleaseField::BugReportUrl => &mut self.bug_report_url,
+            OsReleaseField::DocumentationUrl => &mut self.documentation_url,
+            OsReleaseField::HomeUrl => &mut self.home_url,
+            OsReleaseField::Id => &mut self.id,
+            OsReleaseField::IdLike => &mut self.id_like,
+            OsReleaseField::ImageId => &mut self.image_id,
+            OsReleaseField::Logo => &mut self.logo,
+            OsReleaseField::Name => &mut self.name,
+            OsReleaseField::PrettyName => &mut self.pretty_name,
+            OsReleaseField::PrivacyPolicyUrl => &mut self.privacy_policy_url,
+            OsReleaseField::VariantId => &mut self.variant_id,
+            OsReleaseField::Version => &mut self.version,
+            OsReleaseField::VersionCodename => &mut self.version_codename,
+            OsReleaseField::VersionId => &mut self.version_id,
+            OsReleaseField::SupportEnd => &mut self.support_end,
+            OsReleaseField::SupportUrl => &mut self.support_url,
+        };
+        Some(value)
+    }
+
+    /// Returns the value of the standard `field`.
+    pub fn field(&self, field: OsReleaseField) -> &str {
+        match field {
+            OsReleaseField::AnsiColor => &self.ansi_color,
+            OsReleaseField::BuildId => &self.build_id,
+            OsReleaseField::BugReportUrl => &self.bug_report_url,
+            OsReleaseField::DocumentationUrl => &self.documentation_url,
+            OsReleaseField::HomeUrl => &self.home_url,
+            OsReleaseField::Id => &self.id,
+            OsReleaseField::IdLike => &self.id_like,
+            OsReleaseField::ImageId => &self.image_id,
+            OsReleaseField::Logo => &self.logo,
+            OsReleaseField::Name => &self.name,
+            OsReleaseField::PrettyName => &self.pretty_name,
+            OsReleaseField::PrivacyPolicyUrl => &self.privacy_policy_url,
+            OsReleaseField::VariantId => &self.variant_id,
+            OsReleaseField::Version => &self.version,
+            OsReleaseField::VersionCodename => &self.version_codename,
+            OsReleaseField::VersionId => &self.version_id,
+            OsReleaseField::SupportEnd => &self.support_end,
+            OsReleaseField::SupportUrl => &self.support_url,
+        }
+    }
+
+    /// Returns every standard field, empty or not, as `(field, description, value)`.
+    /// See `OsReleaseField::description`.
+    pub fn described_fields(&self) -> Vec<(OsReleaseField, &'static str, String)> {
+        OsReleaseField::ALL
+            .iter()
+            .map(|&field| (field, field.description(), self.field(field).to_owned()))
+            .collect()
+    }
+
+    /// Returns the URL fields recommended by the specification which are empty:
+    /// `HOME_URL`, `SUPPORT_URL`, `BUG_REPORT_URL` and `PRIVACY_POLICY_URL`, in this order.
+    pub fn missing_recommended(&self) -> Vec<&'static str> {
+        [
+            OsReleaseField::HomeUrl,
+            OsReleaseField::SupportUrl,
+            OsReleaseField::BugReportUrl,
+            OsReleaseField::PrivacyPolicyUrl,
+        ]
+        .iter()
+        .filter(|&&field| self.field(field).is_empty())
+        .map(|field| field.key())
+        .collect()
+    }
+
+    /// Returns the non-empty standard fields, keyed by `OsReleaseField`. Extras are not included.
+    pub fn field_map(&self) -> BTreeMap<OsReleaseField, String> {
+        OsReleaseField::ALL
+            .iter()
+            .map(|&field| (field, self.field(field)))
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(field, value)| (field, value.to_owned()))
+            .collect()
+    }
+
+    /// Returns the standard fields whose value is longer than `max_len` bytes, with their length.
+    /// Lengths are counted in bytes, not characters, since storage limits are in bytes.
+    pub fn fields_exceeding(&self, max_len: usize) -> Vec<(OsReleaseField, usize)> {
+        OsReleaseField::ALL
+            .iter()
+            .map(|&field| (field, self.field(field).len()))
+            .filter(|&(_, len)| len > max_len)
+            .collect()
+    }
+
+    /// Returns the value of `key`, like "VERSION_ID".
+    /// Standard fields are looked up first, then the `extra` map.
+    /// Returns `None` if the key is absent or its value is empty.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let value = self
+            .fields()
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+            .or_else(|| self.extra.get(key).map(String::as_str))?;
+        if value.is_empty() { None } else { Some(value) }
+    }
+
+    /// Checks that every key of `keys`, like "VERSION_ID", has a non-empty value.
+    /// Returns the keys which are absent or empty as an error.
+    pub fn require_keys(&self, keys: &[&str]) -> Result<(), Vec<String>> {
+        let missing: Vec<String> = keys
+            .iter()
+            .filter(|key| self.get(key).is_none())
+            .map(|key| key.to_string())
+            .collect();
+        if missing.is_empty() { Ok(()) } else { Err(missing) }
+    }
+
+    /// Returns the keys of `baseline` whose value differs in this struct.
+    /// Each difference is returned as `(key, current value, baseline value)`,
+    /// the current value being `None` if the key is absent or empty.
+    pub fn diff_from_baseline(&self, baseline: &BTreeMap<String, String>) -> Vec<(String, Option<String>, String)> {
+        baseline
+            .iter()
+            .filter_map(|(key, expected)| {
+                let current = self.get(key);
+                if current == Some(expected.as_str()) {
+                    None
+                } else {
+                    Some((key.clone(), current.map(String::from), expected.clone()))
+                }
+            })
+            .collect()
+    }
+
+    /// Returns `id` followed by the ids of `id_like`, from the most to the least specific.
+    /// Duplicated ids are removed and an empty `id` is omitted.
+    /// For example, on Ubuntu, this is `["ubuntu", "debian"]`.
+    pub fn lineage(&self) -> Vec<&str> {
+        let mut lineage: Vec<&str> = Vec::new();
+        for id in std::iter::once(self.id.as_str()).chain(self.id_like.split_whitespace()) {
+            if !id.is_empty() && !lineage.contains(&id) {
+                lineage.push(id);
+            }
+        }
+        lineage
+    }
+
+    /// Returns `false` if `pretty_name` doesn't contain `name`, which is likely a copy-paste mistake.
+    /// Returns `true` if either of them is empty.
+    /// This is a soft check, a distribution may legitimately use an unrelated pretty name.
+    pub fn pretty_name_consistent(&self) -> bool {
+        self.pretty_name.is_empty() || self.name.is_empty() || self.pretty_name.contains(&self.name)
+    }
+
+    /// Returns `false` if neither `id` nor a distinctive word of `name` appears in `pretty_name`, ignoring case.
+    /// Generic words of names, like "Linux" or "GNU", are ignored, so "Arch Linux" doesn't match "Ubuntu Linux".
+    /// Returns `true` if `pretty_name` is empty, or if both `id` and `name` are empty.
+    /// For example, this is `false` with `ID=arch` and `PRETTY_NAME="Ubuntu 22.04"`.
+    pub fn id_name_consistent(&self) -> bool {
+        if self.pretty_name.is_empty() || (self.id.is_empty() && self.name.is_empty()) {
+            return true;
+        }
+
+        let pretty_name = self.pretty_name.to_lowercase();
+        let id = self.id.to_lowercase();
+        let name = self.name.to_lowercase();
+        (!id.is_empty() && pretty_name.contains(&id))
+            || name
+                .split_whitespace()
+                .filter(|word| !matches!(*word, "linux" | "gnu" | "gnu/linux" | "os"))
+                .any(|word| pretty_name.contains(word))
+    }
+
+    /// Returns `(id, pretty_name)` if they likely name different distributions, like after a botched rebrand.
+    /// This is the case when the slugified `pretty_name`, like "ubuntu-22-04", contains neither `id`
+    /// nor the slugified `name`. Returns `None` if `id` or `pretty_name` is empty.
+    /// This is a soft diagnostic, meant for migration tools and linters.
+    pub fn brand_conflict(&self) -> Option<(String, String)> {
+        if self.id.is_empty() || self.pretty_name.is_empty() {
+            return None;
+        }
+
+        let pretty_name = slugify(&self.pretty_name);
+        let name = slugify(&self.name);
+        if pretty_name.contains(&slugify(&self.id)) || (!name.is_empty() && pretty_name.contains(&name)) {
+            None
+        } else {
+            Some((self.id.clone(), self.pretty_name.clone()))
+        }
+    }
+
+    /// Expands `${KEY}` references in every value with the value of `KEY`.
+    /// Uses `DEFAULT_EXPANSION_LIMIT` as the maximum number of expansion passes.
+    /// See `OsRelease::expand_variables_with_limit`.
+    pub fn expand_variables(&mut self) {
+        self.expand_variables_with_limit(DEFAULT_EXPANSION_LIMIT);
+    }
+
+    /// Expands `${KEY}` references in every value with the value of `KEY`.
+    /// References to keys which are absent or empty are left as-is.
+    /// Each pass replaces references with the values of the previous pass,
+    /// so `A=${B}`, `B=${C}` and `C=c` need two passes.
+    /// Expansion stops after `limit` passes, leaving the remaining references as-is,
+    /// so self-referencing or deeply nested values can't loop forever.
+    /// Expansions which would make a value longer than 4096 bytes are skipped,
+    /// so values referencing themselves several times, like `A="x${A}${A}"`, can't grow exponentially.
+    pub fn expand_variables_with_limit(&mut self, limit: usize) {
+        for _ in 0..limit {
+            let snapshot = self.clone();
+            let mut changed = false;
+
+            let keys: Vec<&str> = snapshot.fields().iter().map(|(key, _)| *key)
+                .chain(snapshot.extra.keys().map(String::as_str))
+                .collect();
+            for key in keys {
+                let value = snapshot.get(key).unwrap_or_default();
+                if !value.contains("${") {
+                    continue;
+                }
+
+                let expanded = expand_references(value, |name| snapshot.get(name));
+                if expanded != value && expanded.len() <= MAX_EXPANDED_LEN {
+                    changed = true;
+                    match self.field_mut(key) {
+                        Some(field) => *field = expanded,
+                        None => { self.extra.insert(key.to_owned(), expanded); }
+                    }
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// Returns the name of the operating system to show to users.
+    /// This is `pretty_name` if set, else `name` followed by `version`, like "Arch Linux".
+    /// If `name` isn't set either, this is "Linux", the default of the specification.
+    pub fn display_name(&self) -> String {
+        if !self.pretty_name.is_empty() {
+            return self.pretty_name.clone();
+        }
+
+        let name = if self.name.is_empty() { "Linux" } else { &self.name };
+        if self.version.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{} {}", name, self.version)
+        }
+    }
+
+    /// Writes the struct as an os-release file to `writer`, according to `options`.
+    /// Without options, this writes the same content as `Display`.
+    pub fn write_with_options<W: io::Write>(&self, mut writer: W, options: &WriteOptions) -> io::Result<()> {
+        if options.synthesize_pretty_name && self.pretty_name.is_empty() {
+            let os_release = OsRelease { pretty_name: self.display_name(), ..self.clone() };
+            return write!(writer, "{}", os_release);
+        }
+        write!(writer, "{}", self)
+    }
+
+    /// Returns the extras to write, as `(key, value)`.
+    /// Standard keys copied in `extra` by `ParseOptions::include_standard_in_extra` are skipped,
+    /// their value is already written from the field.
+    fn written_extra(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.extra
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .filter(|(key, _)| OsReleaseField::from_key(key).is_none())
+    }
+
+    /// Returns every non-empty field and every extra as a `KEY="value"` line, quoted for the shell.
+    /// Standard fields come first, then extras. This is the content of `Display`, line by line.
+    pub fn to_lines(&self) -> Vec<String> {
+        let extra = self.written_extra();
+        self.fields()
+            .into_iter()
+            .chain(extra)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| format!("{}={}", key, quote(value)))
+            .collect()
+    }
+
+    /// Parses each `KEY=value` line, passes the key and the unquoted value to `f`, and returns the lines
+    /// it returns as canonical `KEY="value"` lines, quoted for the shell. `f` can rewrite a line by returning
+    /// another key or value, or drop it by returning `None`. Comments and blank lines are dropped.
+    /// Lines are streamed, the content isn't parsed into a struct, so the order and duplicates are kept.
+    pub fn transform_lines<'a, F>(lines: impl Iterator<Item = &'a str>, mut f: F) -> String
+    where
+        F: FnMut(&str, &str) -> Option<(String, String)>,
+    {
+        let options = ParseOptions::default();
+        let mut output = String::new();
+        for line in lines {
+            let (key, value) = match split_line(line, &options) {
+                Some((key, value)) => (key, decode_value(value, &options)),
+                None => continue,
+            };
+            if let Some((key, value)) = f(key, &value) {
+                output.push_str(&format!("{}={}\n", key, quote(&value)));
+            }
+        }
+        output
+    }
+
+    /// Returns every non-empty field and every extra as `(key, value)`, sorted by key,
+    /// so the result doesn't depend on the order of the struct fields, like for reproducible builds.
+    pub fn sorted_pairs(&self) -> Vec<(String, String)> {
+        let extra = self.written_extra();
+        let mut pairs: Vec<(String, String)> = self.fields()
+            .into_iter()
+            .chain(extra)
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Returns a shell script exporting every non-empty field and every extra as an environment variable.
+    /// Each value is emitted as `export KEY="value"` on its own line, quoted for the shell,
+    /// so the script can be sourced or installed as a `/etc/profile.d` snippet.
+    pub fn to_export_script(&self) -> String {
+        self.to_lines().iter().map(|line| format!("export {}\n", line)).collect()
+    }
+
+    /// Returns a canonical representation of the struct, to be hashed or signed.
+    /// The canonical form is stable for a given set of values, whatever the formatting of the source:
+    /// - Every non-empty standard field and every non-empty extra is emitted, empty ones are omitted.
+    /// - Entries are sorted by key, comparing the UTF-8 bytes of the keys.
+    /// - Each entry is a `KEY="value"` line, ending with a single `\n`, including the last one.
+    /// - Values are always enclosed in double quotes, and `"`, `\`, `$` and `` ` `` are escaped with `\`.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let extra = self.written_extra();
+        let mut entries: Vec<(&str, &str)> = self.fields()
+            .into_iter()
+            .chain(extra)
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+        entries.sort_unstable();
+
+        let mut bytes = Vec::new();
+        for (key, value) in entries {
+            bytes.extend_from_slice(format!("{}={}\n", key, quote(value)).as_bytes());
+        }
+        bytes
+    }
+
+    /// Merges the fields and extras of `other` into this struct.
+    /// Values missing in this struct are taken from `other`, values missing in `other` are kept.
+    /// When both have a different value for a key, `resolve(key, mine, theirs)` returns the value to keep.
+    pub fn merge_with<F: Fn(&str, &str, &str) -> String>(&mut self, other: &OsRelease, resolve: F) {
+        let extra = other.extra.iter().map(|(key, value)| (key.as_str(), value.as_str()));
+        for (key, theirs) in other.fields().into_iter().chain(extra) {
+            if theirs.is_empty() {
+                continue;
+            }
+
+            let merged = match self.get(key) {
+                None => theirs.to_owned(),
+                Some(mine) if mine == theirs => continue,
+                Some(mine) => resolve(key, mine, theirs),
+            };
+            match self.field_mut(key) {
+                Some(field) => *field = merged,
+                None => { self.extra.insert(key.to_owned(), merged); }
+            }
+        }
+    }
+
+    /// Fills the empty fields and the missing extras of this struct from `defaults`,
+    /// leaving the values already set untouched, like `/usr/lib/os-release` provides defaults for `/etc/os-release`.
+    pub fn fill_defaults(&mut self, defaults: &OsRelease) {
+        self.merge_with(defaults, |_, mine, _| mine.to_owned());
+    }
+
+    /// Consumes the struct and returns every non-empty field and extra in a single map.
+    /// Standard fields are stored under their uppercase key, like "VERSION_ID".
+    pub fn into_extra(mut self) -> BTreeMap<String, String> {
+        let keys: Vec<&'static str> = self.fields().iter().map(|(key, _)| *key).collect();
+        let mut map = std::mem::take(&mut self.extra);
+        for key in keys {
+            let value = self.field_mut(key).map(std::mem::take).unwrap_or_default();
+            if !value.is_empty() {
+                map.insert(key.to_owned(), value);
+            }
+        }
+        map
+    }
+
+    /// Compares the `IMAGE_VERSION` extra of this struct with the one of `other`, like "2023.1" and "2023.2",
+    /// comparing its dot-separated components as numbers.
+    /// Returns `None` if either is empty or not numeric.
+    pub fn image_version_cmp(&self, other: &OsRelease) -> Option<Ordering> {
+        let image_version = |os_release: &OsRelease| {
+            version_components(os_release.extra.get("IMAGE_VERSION").map_or("", String::as_str))
+        };
+        Some(compare_versions(&image_version(self)?, &image_version(other)?))
+    }
+
+    /// Returns `true` if moving from `older` to this release is an upgrade of the same distribution.
+    /// This is the case when `id` is the same, ignoring case, and `version_id` is strictly greater
+    /// than the one of `older`, comparing its components as numbers.
+    /// Two snapshots of a rolling release, without `version_id`, are also considered an upgrade.
+    pub fn is_upgrade_from(&self, older: &OsRelease) -> bool {
+        if !self.id.eq_ignore_ascii_case(&older.id) {
+            return false;
+        }
+        if self.version_id.is_empty() && older.version_id.is_empty() {
+            return true;
+        }
+
+        match (version_components(&self.version_id), version_components(&older.version_id)) {
+            (Some(new), Some(old)) => compare_versions(&new, &old) == Ordering::Greater,
+            _ => false,
+        }
+    }
+
+    /// Returns every `http://` or `https://` URL found in the fields and extras, without duplicates.
+    /// URLs are also searched inside values, like a URL embedded in `VERSION`,
+    /// and end at the first whitespace, quote, angle bracket or closing parenthesis.
+    pub fn all_urls(&self) -> Vec<String> {
+        let extra = self.extra.values().map(String::as_str);
+        let mut urls: Vec<String> = Vec::new();
+        for value in self.fields().iter().map(|(_, value)| *value).chain(extra) {
+            let mut rest = value;
+            while let Some(start) = ["http://", "https://"].iter().filter_map(|scheme| rest.find(scheme)).min() {
+                let url = &rest[start..];
+                let end = url
+                    .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | ')'))
+                    .unwrap_or(url.len());
+                if !urls.iter().any(|known| known == &url[..end]) {
+                    urls.push(url[..end].to_owned());
+                }
+                rest = &url[end..];
+            }
+        }
+        urls
+    }
+
+    /// Returns `true` if `id` or one of the ids of `id_like` is in `ids`.
+    /// For example, Pop!_OS (`ID=pop`, `ID_LIKE="ubuntu debian"`) is compatible with `["debian"]`.
+    pub fn is_compatible_with(&self, ids: &[&str]) -> bool {
+        std::iter::once(self.id.as_str())
+            .chain(self.id_like.split_whitespace())
+            .any(|id| !id.is_empty() && ids.contains(&id))
+    }
+
+    /// Returns the package format of the distribution, like "deb", "rpm" or "pkg.tar".
+    /// The ids of `lineage` are looked up in order in `PACKAGE_FORMATS`,
+    /// so a derivative without its own entry gets the format of its parent.
+    /// Returns `None` if no id is known.
+    pub fn package_format(&self) -> Option<&'static str> {
+        self.lineage().into_iter().find_map(|id| {
+            PACKAGE_FORMATS.iter().find(|(known, _)| *known == id).map(|(_, format)| *format)
+        })
+    }
+
+    /// Returns the family of the distribution, named after its root distribution:
+    /// "debian", "fedora", "suse", "arch", "alpine" or "gentoo".
+    /// The ids of `lineage` are looked up in order in `DISTRIBUTION_FAMILIES`.
+    /// For example, on Ubuntu and its derivatives, this is "debian".
+    /// Returns `None` if no id is known.
+    pub fn family(&self) -> Option<&'static str> {
+        self.lineage().into_iter().find_map(|id| {
+            DISTRIBUTION_FAMILIES.iter().find(|(known, _)| *known == id).map(|(_, family)| *family)
+        })
+    }
+
+    /// Returns the command of the package manager of the distribution family, see `OsRelease::family`:
+    /// "apt", "dnf", "zypper", "pacman", "apk" or "emerge".
+    /// This is best-effort guidance, not a guarantee: the command may not be installed,
+    /// and older releases may use another one, like `yum` on CentOS 7.
+    /// Returns `None` for unknown distributions.
+    pub fn package_manager(&self) -> Option<&'static str> {
+        match self.family()? {
+            "debian" => Some("apt"),
+            "fedora" => Some("dnf"),
+            "suse" => Some("zypper"),
+            "arch" => Some("pacman"),
+            "alpine" => Some("apk"),
+            "gentoo" => Some("emerge"),
+            _ => None,
+        }
+    }
+
+    /// Returns the extra keys which don't follow the `[A-Z][A-Z0-9_]*` grammar of the specification,
+    /// like `1FOO` or `foo`. This doesn't change parsing, it is meant for linters.
+    pub fn invalid_keys(&self) -> Vec<&str> {
+        self.extra.keys().map(String::as_str).filter(|key| !is_valid_key(key)).collect()
+    }
+
+    /// Returns the keys whose value wouldn't survive writing the struct with `Display` and parsing it back:
+    /// extras with a key outside of the `[A-Z][A-Z0-9_]*` grammar, like `MY KEY`,
+    /// and fields or extras whose value is changed by the round-trip, like a value with a newline.
+    /// Standard fields come first, then extras.
+    pub fn lossy_fields(&self) -> Vec<&str> {
+        let reparsed = OsRelease::from_iter(self.to_string().lines().map(String::from));
+        let fields = self.fields()
+            .into_iter()
+            .filter(|(key, value)| reparsed.get(key).unwrap_or_default() != *value)
+            .map(|(key, _)| key);
+        let extra = self.written_extra()
+            .filter(|(key, value)| !is_valid_key(key) || reparsed.extra.get(*key).map(String::as_str) != Some(*value))
+            .map(|(key, _)| key);
+        fields.chain(extra).collect()
+    }
+
+    /// Returns a builder for a distribution derived from `base`, like Manjaro from Arch Linux.
+    /// `id_like` is the `id_like` of `base` followed by its `id`, and the URLs of `base` are copied.
+    /// The other fields, like `id` and `name`, are left empty to be set by the caller.
+    pub fn derive_from(base: &OsRelease) -> OsReleaseBuilder {
+        let id_like = base.id_like
+            .split_whitespace()
+            .chain(base.id.split_whitespace())
+            .collect::<Vec<_>>()
+            .join(" ");
+        OsReleaseBuilder::new()
+            .id_like(id_like)
+            .home_url(base.home_url.as_str())
+            .documentation_url(base.documentation_url.as_str())
+            .support_url(base.support_url.as_str())
+            .bug_report_url(base.bug_report_url.as_str())
+            .privacy_policy_url(base.privacy_policy_url.as_str())
+    }
+
+    /// Splits the extras in `(reserved_unknown, vendor)`.
+    /// Keys following the `[A-Z][A-Z0-9_]*` grammar are reserved by the specification,
+    /// so the ones which aren't standard may become standard keys, like `FUTURE_KEY`.
+    /// The other keys, like `x-custom`, are clearly vendor extensions.
+    /// Standard keys copied in `extra` by `ParseOptions::include_standard_in_extra` are in neither map.
+    pub fn partition_extras(&self) -> (BTreeMap<&str, &str>, BTreeMap<&str, &str>) {
+        self.extra
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .filter(|(key, _)| OsReleaseField::from_key(key).is_none())
+            .partition(|(key, _)| is_valid_key(key))
+    }
+
+    /// Returns the extras whose value starts with `http://` or `https://`,
+    /// like vendor-specific links such as `VENDOR_URL`.
+    pub fn url_extras(&self) -> BTreeMap<&str, &str> {
+        self.extra
+            .iter()
+            .filter(|(_, value)| value.starts_with("http://") || value.starts_with("https://"))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    /// Returns the extras whose key starts with `SYSTEMD_`.
+    pub fn systemd_extras(&self) -> BTreeMap<&str, &str> {
+        self.extra
+            .iter()
+            .filter(|(key, _)| key.starts_with("SYSTEMD_"))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+
+    /// Guesses `id` from `pretty_name`, or from `name` if `pretty_name` is empty, when `id` is empty.
+    /// The guessed id is the first word of the name, lowercased, without non-alphanumeric characters.
+    /// For example, "Arch Linux" gives "arch".
+    /// This is opt-in since the guess may be wrong, `id` is left untouched if it is already set.
+    pub fn recover_id(&mut self) {
+        if !self.id.is_empty() {
+            return;
+        }
+
+        let name = if self.pretty_name.is_empty() { &self.name } else { &self.pretty_name };
+        if let Some(word) = name.split_whitespace().next() {
+            self.id = word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+        }
+    }
+
+    /// Returns the keys whose value differs between this struct and `other`, sorted by key.
+    /// Each difference is returned as `(key, value in self, value in other)`,
+    /// a value being `None` if the key is absent or empty.
+    pub fn diff(&self, other: &OsRelease) -> Vec<(String, Option<String>, Option<String>)> {
+        let mut keys: Vec<&str> = self.fields().iter().map(|(key, _)| *key)
+            .chain(self.extra.keys().map(String::as_str))
+            .chain(other.extra.keys().map(String::as_str))
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        keys.into_iter()
+            .filter(|key| self.get(key) != other.get(key))
+            .map(|key| (key.to_owned(), self.get(key).map(String::from), other.get(key).map(String::from)))
+            .collect()
+    }
+
+    /// Returns the differences between this struct and `other` as human-readable lines.
+    /// A changed key gives a `- KEY=old` line followed by a `+ KEY=new` line,
+    /// an added or removed key gives only one of them.
+    pub fn diff_pretty(&self, other: &OsRelease) -> String {
+        let mut pretty = String::new();
+        for (key, old, new) in self.diff(other) {
+            if let Some(old) = old {
+                pretty.push_str(&format!("- {}={}\n", key, old));
+            }
+            if let Some(new) = new {
+                pretty.push_str(&format!("+ {}={}\n", key, new));
+            }
+        }
+        pretty
+    }
+
+    /// Returns the differences between the extras of this struct and the ones of `other`.
+    /// Unlike `diff`, standard fields are ignored.
+    pub fn extra_diff(&self, other: &OsRelease) -> ExtraDiff {
+        let mut diff = ExtraDiff::default();
+        for (key, old) in &self.extra {
+            match other.extra.get(key) {
+                None => diff.removed.push((key.clone(), old.clone())),
+                Some(new) if new != old => diff.changed.push((key.clone(), old.clone(), new.clone())),
+                Some(_) => (),
+            }
+        }
+        for (key, new) in &other.extra {
+            if !self.extra.contains_key(key) {
+                diff.added.push((key.clone(), new.clone()));
+            }
+        }
+        diff
+    }
+
+    /// Returns a terse, id-centric summary of the distribution for logs:
+    /// `id`, then `version_id` or "rolling" if it is empty, then `pretty_name` between parentheses.
+    /// Empty parts are skipped. For example, on ArchLinux, this is "arch rolling (Arch Linux)".
+    pub fn summary(&self) -> String {
+        let version = if self.version_id.is_empty() { "rolling" } else { &self.version_id };
+        let mut parts: Vec<String> = vec![];
+        if !self.id.is_empty() {
+            parts.push(self.id.clone());
+        }
+        parts.push(version.to_owned());
+        if !self.pretty_name.is_empty() {
+            parts.push(format!("({})", self.pretty_name));
+        }
+        parts.join(" ")
+    }
+
+    /// Guesses the edition of the distribution: "server", "desktop" or "minimal".
+    /// This is a heuristic: `variant_id`, then the `VARIANT` extra, then `pretty_name` are searched,
+    /// ignoring case, for the keywords of `EDITION_KEYWORDS`, like "workstation" for "desktop".
+    /// Returns `None` if none of them contains a keyword.
+    pub fn guessed_edition(&self) -> Option<&'static str> {
+        let variant = self.extra.get("VARIANT").map_or("", String::as_str);
+        [self.variant_id.as_str(), variant, self.pretty_name.as_str()]
+            .iter()
+            .map(|value| value.to_lowercase())
+            .find_map(|value| {
+                EDITION_KEYWORDS.iter().find(|(keyword, _)| value.contains(keyword)).map(|(_, edition)| *edition)
+            })
+    }
+
+    /// Returns a stable token for compatibility databases: `id`, followed by `:` and the first two components
+    /// of `version_id` if it is set. For example, this is "arch" on ArchLinux and "ubuntu:22.04" on Ubuntu 22.04.3.
+    /// This is empty if `id` isn't set.
+    pub fn compat_token(&self) -> String {
+        if self.id.is_empty() || self.version_id.is_empty() {
+            return self.id.clone();
+        }
+        let version: Vec<&str> = self.version_id.split('.').take(2).collect();
+        format!("{}:{}", self.id, version.join("."))
+    }
+
+    /// Returns `logo` without its `-logo` suffix, if any.
+    /// For example, on ArchLinux, this is "archlinux".
+    pub fn logo_base(&self) -> &str {
+        self.logo.strip_suffix("-logo").unwrap_or(&self.logo)
+    }
+
+    /// Returns `true` if the distribution is a development or pre-release build.
+    /// This is a heuristic, it returns `true` when one of these is true:
+    /// - `version_codename`, `build_id` or `version` contains one of the markers of
+    ///   `PRERELEASE_MARKERS` as a word, ignoring case and trailing digits, like "rawhide", "sid" or "rc1".
+    /// - `pretty_name` contains "(development)", like on Ubuntu development releases.
+    ///   Markers aren't searched in `pretty_name`, since names like "Testing Ground OS" contain them.
+    pub fn is_prerelease(&self) -> bool {
+        let has_marker = |value: &str| {
+            value.split(|c: char| !c.is_alphanumeric()).any(|word| {
+                let word = word.to_lowercase();
+                let word = word.trim_end_matches(|c: char| c.is_ascii_digit());
+                PRERELEASE_MARKERS.contains(&word)
+            })
+        };
+
+        has_marker(&self.version_codename)
+            || has_marker(&self.build_id)
+            || has_marker(&self.version)
+            || self.pretty_name.contains("(development)")
+    }
+
+    /// Returns `true` if the distribution is SteamOS or derived from it.
+    /// This is a heuristic, it returns `true` when one of these is true:
+    /// - `id` is "steamos" or `id_like` contains "steamos".
+    /// - `id_like` contains "arch" and `variant_id` contains "steam", like "steamdeck".
+    pub fn is_steamos(&self) -> bool {
+        let is_like = |parent: &str| self.id_like.split_whitespace().any(|id| id == parent);
+        self.id == "steamos"
+            || is_like("steamos")
+            || (is_like("arch") && self.variant_id.contains("steam"))
+    }
+
+    /// Returns `true` if the os-release file marks the distribution as built for WSL.
+    /// This is a conservative heuristic, it returns `true` when one of these is true:
+    /// - An extra key starts with `WSL_`, like `WSL_DISTRIBUTION`, as added by some WSL images.
+    /// - `variant_id` is "wsl".
+    /// - `id` is "pengwin", a distribution only shipped for WSL.
+    ///
+    /// A stock distribution running under WSL has a normal os-release file, so this returns `false` for it.
+    /// The kernel release, in `/proc/sys/kernel/osrelease`, is a better hint in that case.
+    pub fn is_wsl(&self) -> bool {
+        self.extra.keys().any(|key| key.starts_with("WSL_"))
+            || self.variant_id == "wsl"
+            || self.id == "pengwin"
+    }
+
+    /// Returns the SUSE-specific information of a SUSE distribution, like SLES or openSUSE Leap.
+    /// This is the case when `lineage` contains "suse", "opensuse" or "sles", else `None` is returned.
+    /// The service pack is read, in this order, from:
+    /// - The `SUSE_SP`, `SPVERSION` or `PATCHLEVEL` extras, written by some products.
+    /// - The `-SP` suffix of `version`, like "15-SP5".
+    /// - The minor component of `version_id` on SLES, like "15.5".
+    ///
+    /// The extras are left untouched in `extra`.
+    pub fn suse_info(&self) -> Option<SuseInfo> {
+        if !self.lineage().iter().any(|id| matches!(*id, "suse" | "opensuse" | "sles")) {
+            return None;
+        }
+
+        let cpe_product = self.extra.get("CPE_NAME").and_then(|cpe| cpe.split(':').nth(3));
+        let product = cpe_product.filter(|product| !product.is_empty()).unwrap_or(&self.id).to_owned();
+        let components = version_components(&self.version_id);
+        let service_pack = ["SUSE_SP", "SPVERSION", "PATCHLEVEL"]
+            .iter()
+            .find_map(|key| self.extra.get(*key)?.parse().ok())
+            .or_else(|| self.version.split_once("-SP")?.1.split_whitespace().next()?.parse().ok())
+            .or_else(|| match &components {
+                Some(components) if self.id == "sles" => components.get(1).copied(),
+                _ => None,
+            });
+        Some(SuseInfo {
+            product,
+            major: components.and_then(|components| components.first().copied()),
+            service_pack,
+        })
+    }
+
+    /// Returns `(labeled, actual)` if the `ARCHITECTURE` extra is set and doesn't match the architecture
+    /// of the running system, like `("arm64", "x86-64")` for an arm64 image inspected on an x86-64 host.
+    /// Common alternative names are accepted, like "amd64" or "aarch64". Returns `None` otherwise.
+    pub fn architecture_mismatch(&self) -> Option<(String, String)> {
+        let labeled = self.extra.get("ARCHITECTURE").filter(|labeled| !labeled.is_empty())?;
+        let actual = current_architecture();
+        if architecture_name(labeled) == actual {
+            None
+        } else {
+            Some((labeled.clone(), actual.to_owned()))
+        }
+    }
+
+    /// Returns `true` if the distribution is an Enterprise Linux (RHEL or one of its clones).
+    /// This is the case when `id` is in the list of known clones (see `ENTERPRISE_LINUX_IDS`)
+    /// or when `id_like` contains `rhel`.
+    /// For example, this is `true` on AlmaLinux and Rocky Linux but `false` on Fedora,
+    /// which is upstream of RHEL and not a clone.
+    pub fn is_enterprise_linux(&self) -> bool {
+        ENTERPRISE_LINUX_IDS.contains(&self.id.as_str())
+            || self.id_like.split_whitespace().any(|id| id == "rhel")
+    }
+
+    /// Returns `true` if the distribution is an Ubuntu LTS release.
+    /// This is the case when `id` is "ubuntu" and either `version` contains "LTS"
+    /// or `version_id` is an even year followed by ".04", like "22.04".
+    pub fn is_ubuntu_lts(&self) -> bool {
+        if self.id != "ubuntu" {
+            return false;
+        }
+
+        let lts_version_id = match self.version_id.split_once('.') {
+            Some((year, "04")) => year.parse::<u32>().is_ok_and(|year| year % 2 == 0),
+            _ => false,
+        };
+        self.version.contains("LTS") || lts_version_id
+    }
+
+    /// Parses `version_id` as a semantic version.
+    /// This only works for distributions using a full `MAJOR.MINOR.PATCH` version id,
+    /// for example Alpine Linux ("3.18.4") or openSUSE Leap Micro.
+    /// Returns `None` for other forms like Ubuntu's "22.04" or Fedora's "39",
+    /// and for rolling releases without a version id.
+    #[cfg(feature = "semver")]
+    pub fn semver(&self) -> Option<semver::Version> {
+        semver::Version::parse(&self.version_id).ok()
+    }
+
+    /// Returns the JSON schema of `OsRelease` as a string.
+    /// Can be used to validate `OsRelease` objects serialized as JSON.
+    #[cfg(feature = "schemars")]
+    pub fn json_schema_string() -> String {
+        schemars::schema_for!(OsRelease).as_value().to_string()
+    }
+
+    /// Returns the JSON Schema of the JSON object built by `From<&OsRelease> for serde_json::Value`.
+    /// Every standard key, like `NAME`, is an optional string property described by `OsReleaseField::description`,
+    /// and additional string properties are allowed for the extras.
+    #[cfg(feature = "json")]
+    pub fn json_schema() -> serde_json::Value {
+        let properties: serde_json::Map<String, serde_json::Value> = OsReleaseField::ALL
+            .iter()
+            .map(|field| (field.key().to_owned(), serde_json::json!({
+                "type": "string",
+                "description": field.description(),
+            })))
+            .collect();
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "os-release",
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": { "type": "string" },
+        })
+    }
+
+    /// Returns `version`, `version_id` and `version_codename` bundled together
+    /// with the numeric components of `version_id` and the codename of the version.
+    /// See `VersionInfo` for the source of each field.
+    pub fn version_info(&self) -> VersionInfo {
+        let numeric = version_components(&self.version_id).map(|components| {
+            let component = |i: usize| components.get(i).copied().unwrap_or(0);
+            (component(0), component(1), component(2))
+        });
+
+        let codename = if !self.version_codename.is_empty() {
+            Some(self.version_codename.clone())
+        } else {
+            self.version
+                .split_once('(')
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .map(|(codename, _)| codename.trim().to_owned())
+                .filter(|codename| !codename.is_empty())
+        };
+
+        VersionInfo {
+            version: self.version.clone(),
+            version_id: self.version_id.clone(),
+            version_codename: self.version_codename.clone(),
+            numeric,
+            codename,
+        }
+    }
+
+    /// Returns the year of the release if `version_id` is date-based, either `YYYY` or `YY.MM`
+    /// followed by other components, like "2023.1" or "22.04" for 2022.
+    /// Returns `None` for other versions, like "12" or "9.3".
+    pub fn release_year(&self) -> Option<i32> {
+        let mut components = self.version_id.split('.');
+        let first = components.next()?;
+        let second = components.next().unwrap_or_default();
+        if !first.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let year = first.parse().ok()?;
+        match first.len() {
+            4 if (1990..=2100).contains(&year) => Some(year),
+            2 if second.len() == 2 && second.parse().is_ok_and(|month: u8| (1..=12).contains(&month)) => Some(2000 + year),
+            _ => None,
+        }
+    }
+
+    /// Returns the release model of the distribution, by applying these rules in order:
+    /// - `Rolling` if `build_id` or `version_id` is "rolling", or if `id` is in `ROLLING_IDS`, like "gentoo".
+    /// - `DateBased` if `version_id` encodes a year, see `OsRelease::release_year`.
+    /// - `Pointed` if `version_id` is numeric, like "12" or "9.3".
+    /// - `Unknown` otherwise.
+    pub fn release_model(&self) -> ReleaseModel {
+        if self.build_id == "rolling" || self.version_id == "rolling" || ROLLING_IDS.contains(&self.id.as_str()) {
+            ReleaseModel::Rolling
+        } else if self.release_year().is_some() {
+            ReleaseModel::DateBased
+        } else if version_components(&self.version_id).is_some() {
+            ReleaseModel::Pointed
+        } else {
+            ReleaseModel::Unknown
+        }
+    }
+
+    /// Returns the kind of build, detected from `build_id`:
+    /// - `Rolling` if it is "rolling".
+    /// - `Dated` if it starts with an ISO date (`YYYY-MM-DD`), like "2024-01-15".
+    /// - `Commit` if it is a hexadecimal commit hash of 7 to 40 characters, like "3f2a9c1".
+    ///   A hash has at least one letter, so decimal ids like "20240115" aren't commits.
+    /// - `Other` for any other non-empty value.
+    /// - `None` if it is empty.
+    pub fn build_kind(&self) -> BuildKind {
+        let build_id = self.build_id.as_str();
+        let is_date = build_id.len() >= 10
+            && build_id.bytes().take(10).enumerate().all(|(i, b)| match i {
+                4 | 7 => b == b'-',
+                _ => b.is_ascii_digit(),
+            });
+        let is_commit = (7..=40).contains(&build_id.len())
+            && build_id.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+            && build_id.bytes().any(|b| (b'a'..=b'f').contains(&b));
+
+        if build_id.is_empty() {
+            BuildKind::None
+        } else if build_id == "rolling" {
+            BuildKind::Rolling
+        } else if is_date {
+            BuildKind::Dated(build_id.to_owned())
+        } else if is_commit {
+            BuildKind::Commit(build_id.to_owned())
+        } else {
+            BuildKind::Other(build_id.to_owned())
+        }
+    }
+
+    /// Returns `false` if `build_id` is "rolling" while `version_id` is set,
+    /// since a rolling release doesn't have versions.
+    pub fn build_id_consistent(&self) -> bool {
+        self.build_id != "rolling" || self.version_id.is_empty()
+    }
+
+    /// Parse `support_end` as a `(year, month, day)` date.
+    /// Returns `None` if `support_end` is empty, not in the `YYYY-MM-DD` format,
+    /// or not an existing date, like "2024-02-30".
+    pub fn support_end_date(&self) -> Option<(i32, u8, u8)> {
+        let mut parts = self.support_end.splitn(3, '-');
+        let year = parts.next()?;
+        let month = parts.next()?;
+        let day = parts.next()?;
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return None;
+        }
+
+        let date = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+        if (1..=12).contains(&date.1) && (1..=days_in_month(date.0, date.1)).contains(&date.2) {
+            Some(date)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `Some(true)` if today, in UTC, is past `support_end`, and `Some(false)` if it isn't.
+    /// Support ends after the `support_end` day, so this is `Some(false)` on that day.
+    /// Returns `None` if `support_end` isn't set or isn't a valid date.
+    #[cfg(feature = "chrono")]
+    pub fn is_end_of_life(&self) -> Option<bool> {
+        let (year, month, day) = self.support_end_date()?;
+        let end = chrono::NaiveDate::from_ymd_opt(year, u32::from(month), u32::from(day))?;
+        Some(chrono::Utc::now().date_naive() > end)
+    }
+
+    /// Returns the support status of the distribution at the date `today`, given as `(year, month, day)`.
+    /// Support is `EndingSoon` when it ends in `soon_days` days or less, for example 90.
+    /// Returns `SupportStatus::Unknown` if `support_end` isn't set or isn't a valid date.
+    pub fn support_status(&self, today: (i32, u8, u8), soon_days: u32) -> SupportStatus {
+        let end = match self.support_end_date() {
+            Some(end) => end,
+            None => return SupportStatus::Unknown,
+        };
+
+        let days_left = days_from_civil(end) - days_from_civil(today);
+        if days_left < 0 {
+            SupportStatus::Ended(days_left.unsigned_abs() as u32)
+        } else if days_left <= i64::from(soon_days) {
+            SupportStatus::EndingSoon(days_left as u32)
+        } else {
+            SupportStatus::Supported
+        }
+    }
+
+    /// Returns `true` if `version_id` satisfies `constraint`.
+    /// The constraint is an operator (`>=`, `<=`, `>`, `<` or `=`) followed by a version, like ">=22.04".
+    /// Without an operator, `=` is assumed.
+    /// A version ending with `.*`, like "8.*", matches every version starting with these components.
+    /// Versions are compared component by component as numbers, so "22.10" is greater than "22.04".
+    /// Returns `false` if `version_id` is empty or not numeric, or if the constraint can't be parsed.
+    pub fn version_satisfies(&self, constraint: &str) -> bool {
+        let version = match version_components(&self.version_id) {
+            Some(version) => version,
+            None => return false,
+        };
+
+        let constraint = constraint.trim();
+        let (operator, expected) = [">=", "<=", ">", "<", "="]
+            .iter()
+            .find_map(|operator| constraint.strip_prefix(operator).map(|rest| (*operator, rest.trim())))
+            .unwrap_or(("=", constraint));
+
+        if let Some(prefix) = expected.strip_suffix(".*") {
+            return match (operator, version_components(prefix)) {
+                ("=", Some(prefix)) => version.starts_with(&prefix),
+                _ => false,
+            };
+        }
+
+        let expected = match version_components(expected) {
+            Some(expected) => expected,
+            None => return false,
+        };
+        let ordering = compare_versions(&version, &expected);
+        match operator {
+            ">=" => ordering != Ordering::Less,
+            "<=" => ordering != Ordering::Greater,
+            ">" => ordering == Ordering::Greater,
+            "<" => ordering == Ordering::Less,
+            _ => ordering == Ordering::Equal,
+        }
+    }
+
+    /// Returns the struct as a TOML document.
+    /// Non-empty standard fields are top-level keys named like the struct fields, like `id`,
+    /// and extras are in an `[extra]` table with their original keys.
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> String {
+        let mut table = toml::Table::new();
+        for (key, value) in self.fields() {
+            if !value.is_empty() {
+                table.insert(key.to_lowercase(), value.into());
+            }
+        }
+        let extra: toml::Table = self.written_extra().map(|(key, value)| (key.to_owned(), value.into())).collect();
+        if !extra.is_empty() {
+            table.insert("extra".to_owned(), toml::Value::Table(extra));
+        }
+        table.to_string()
+    }
+
+    /// Parse os-release data embedded in a JSON document, like cloud metadata.
+    /// `pointer` is a JSON pointer, like "/os/release", to an object whose keys are os-release keys,
+    /// like `{"ID": "arch", "NAME": "Arch Linux"}`. Numbers and booleans are converted to strings,
+    /// other non-string values are ignored.
+    /// Returns `OsReleaseError::JsonPointer` if `pointer` doesn't lead to an object.
+    #[cfg(feature = "json")]
+    pub fn from_metadata_json(json: &str, pointer: &str) -> Result<OsRelease, OsReleaseError> {
+        let document: serde_json::Value = serde_json::from_str(json).map_err(OsReleaseError::Json)?;
+        let object = document
+            .pointer(pointer)
+            .and_then(serde_json::Value::as_object)
+            .ok_or_else(|| OsReleaseError::JsonPointer(pointer.to_owned()))?;
+
+        let mut os_release = OsRelease::default();
+        for (key, value) in object {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.to_string(),
+                _ => continue,
+            };
+            os_release.insert_value(key, &value);
+        }
+        Ok(os_release)
+    }
+
+    /// Returns the struct as an INI document, readable with the `ini` crate.
+    /// Non-empty standard fields are in an `[os-release]` section, named like the struct fields, like `name`,
+    /// and extras are in an `[extra]` section with their original keys.
+    /// Values are escaped following the INI conventions of the `ini` crate.
+    #[cfg(feature = "ini")]
+    pub fn to_ini(&self) -> String {
+        let mut ini = ini::Ini::new();
+        for (key, value) in self.fields() {
+            if !value.is_empty() {
+                ini.with_section(Some("os-release")).set(key.to_lowercase(), value);
+            }
+        }
+        for (key, value) in self.written_extra() {
+            ini.with_section(Some("extra")).set(key, value);
+        }
+
+        let mut buffer = Vec::new();
+        ini.write_to(&mut buffer).expect("writing to a Vec can't fail");
+        String::from_utf8(buffer).expect("the ini crate writes valid UTF-8")
+    }
+
+    /// Parse the os-release file of a zip archive.
+    /// Searches for `etc/os-release` first, then for `usr/lib/os-release`.
+    /// Returns `None` if the archive contains neither of them.
+    #[cfg(feature = "zip")]
+    pub fn from_zip<R: Read + Seek>(archive: R) -> io::Result<Option<OsRelease>> {
+        let mut archive = zip::ZipArchive::new(archive).map_err(io::Error::other)?;
+        for path in &["etc/os-release", "usr/lib/os-release"] {
+            match archive.by_name(path) {
+                Ok(file) => return Ok(Some(OsRelease::from_iter(read_lines(BufReader::new(file))?))),
+                Err(zip::result::ZipError::FileNotFound) => continue,
+                Err(why) => return Err(io::Error::other(why)),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parse the os-release file of the image `reference` of the OCI image layout at `layout_dir`.
+    /// `reference` is either the `org.opencontainers.image.ref.name` annotation of the image in `index.json`,
+    /// like "latest", or the digest of its manifest.
+    /// If the reference is a multi-arch index, `platform` selects the image, like "linux/amd64" or "linux/arm64/v8",
+    /// and an `InvalidInput` error is returned if it is `None`.
+    /// Layers are searched from the topmost one, for `etc/os-release` first, then for `usr/lib/os-release`.
+    /// Returns `None` if no layer contains either of them.
+    #[cfg(feature = "oci")]
+    pub fn from_oci_layout<P: AsRef<Path>>(
+        layout_dir: P,
+        reference: &str,
+        platform: Option<&str>,
+    ) -> io::Result<Option<OsRelease>> {
+        let layout_dir = layout_dir.as_ref();
+        let index = oci_json(&layout_dir.join("index.json"))?;
+        let descriptor = oci_descriptors(&index)
+            .find(|descriptor| {
+                descriptor["digest"] == reference
+                    || descriptor["annotations"]["org.opencontainers.image.ref.name"] == reference
+            })
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no image {:?} in the OCI layout at {:?}", reference, layout_dir),
+            ))?;
+
+        let mut manifest = oci_json(&oci_blob_path(layout_dir, descriptor)?)?;
+        if manifest.get("manifests").is_some() {
+            let platform = platform.ok_or_else(|| io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("image {:?} is a multi-arch index, a platform is required", reference),
+            ))?;
+            let descriptor = oci_descriptors(&manifest)
+                .find(|descriptor| oci_platform(descriptor) == platform)
+                .ok_or_else(|| io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no {} image for {:?}", platform, reference),
+                ))?;
+            manifest = oci_json(&oci_blob_path(layout_dir, descriptor)?)?;
+        }
+
+        let layers = manifest["layers"].as_array().cloned().unwrap_or_default();
+        let mut fallback = None;
+        for layer in layers.iter().rev() {
+            let blob = open(oci_blob_path(layout_dir, layer)?)?;
+            let media_type = layer["mediaType"].as_str().unwrap_or_default();
+            let found = if media_type.ends_with("gzip") {
+                tar_os_release(&mut tar::Archive::new(flate2::read::GzDecoder::new(blob)))?
+            } else {
+                tar_os_release(&mut tar::Archive::new(blob))?
+            };
+            match found {
+                Some((os_release, true)) => return Ok(Some(os_release)),
+                Some((os_release, false)) => { fallback.get_or_insert(os_release); }
+                None => (),
+            }
+        }
+        Ok(fallback)
+    }
+
+    /// Parse the os-release file of a gzip-compressed tar stream, like a container layer.
+    /// `etc/os-release` is preferred over `usr/lib/os-release`, the stream is read only once.
+    /// Returns `OsReleaseError::NotGzip` if the stream doesn't start like a gzip stream,
+    /// `OsReleaseError::NotTar` if the decompressed stream isn't a tar archive,
+    /// and `OsReleaseError::NoOsRelease` if the archive contains neither file.
+    #[cfg(all(feature = "tar", feature = "gzip"))]
+    pub fn from_tar_gz<R: Read>(stream: R) -> Result<OsRelease, OsReleaseError> {
+        let mut stream = BufReader::new(stream);
+        if !stream.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+            return Err(OsReleaseError::NotGzip);
+        }
+
+        let mut archive = tar::Archive::new(ReadFailures::new(flate2::read::GzDecoder::new(stream)));
+        let result = tar_os_release(&mut archive);
+        let decompression_failed = archive.into_inner().failed;
+        match result {
+            Ok(Some((os_release, _))) => Ok(os_release),
+            Ok(None) => Err(OsReleaseError::NoOsRelease),
+            Err(why) if decompression_failed => Err(OsReleaseError::Io(why)),
+            Err(why) => Err(OsReleaseError::NotTar(why)),
+        }
+    }
+}
+
+impl fmt::Display for OsRelease {
+    /// Writes the struct as an os-release file, see `OsRelease::to_lines`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.to_lines() {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builder of `OsRelease`, with a chainable setter for each field.
+/// For example, to generate an os-release file like the one of the host with another pretty name:
+/// `OsReleaseBuilder::from_current()?.pretty_name("My Linux").build()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OsReleaseBuilder {
+    os_release: OsRelease,
+}
+
+impl OsReleaseBuilder {
+    /// Creates a builder with every field empty.
+    pub fn new() -> OsReleaseBuilder {
+        OsReleaseBuilder::default()
+    }
+
+    /// Creates a builder seeded with the os-release files of the running system.
+    /// See `OsRelease::new`. Returns an error if none of them can be read.
+    pub fn from_current() -> io::Result<OsReleaseBuilder> {
+        OsReleaseBuilder::from_paths(&DEFAULT_PATHS)
+    }
+
+    /// Creates a builder seeded with the os-release files at `paths`, from highest to lowest precedence.
+    /// See `OsRelease::new_from_layers`. Returns an error if none of them can be read.
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P]) -> io::Result<OsReleaseBuilder> {
+        OsRelease::new_from_layers(paths).map(OsReleaseBuilder::from)
+    }
+
+    setters!(
+        ansi_color, build_id, bug_report_url, documentation_url, home_url, id, id_like, image_id,
+        logo, name, pretty_name, privacy_policy_url, variant_id, version, version_codename,
+        version_id, support_end, support_url
+    );
+
+    /// Sets the extra `key` to `value`.
+    pub fn extra<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.os_release.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns the built `OsRelease`.
+    pub fn build(self) -> OsRelease {
+        self.os_release
+    }
+}
+
+/// Available with the `json` feature: the crate has no `serde` feature,
+/// and building the object only needs `serde_json`.
+#[cfg(feature = "json")]
+impl From<&OsRelease> for serde_json::Value {
+    /// Returns a JSON object of the non-empty fields and the extras, with their os-release keys,
+    /// like `{"NAME": "Arch Linux", "ID": "arch"}`.
+    /// Standard keys copied in `extra` by `ParseOptions::include_standard_in_extra` are only written once.
+    fn from(os_release: &OsRelease) -> Self {
+        let object = os_release.fields()
+            .into_iter()
+            .chain(os_release.written_extra())
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| (key.to_owned(), serde_json::Value::String(value.to_owned())))
+            .collect();
+        serde_json::Value::Object(object)
+    }
+}
+
+impl From<OsRelease> for OsReleaseBuilder {
+    fn from(os_release: OsRelease) -> Self {
+        OsReleaseBuilder { os_release }
+    }
+}
+
+/// Parser holding `ParseOptions`, to parse many files with the same options.
+/// For example, to parse files strictly while ignoring `;` comments:
+/// `OsReleaseParser::builder().mode(ParseMode::Strict).comment_chars(vec!['#', ';']).build()`.
+/// `OsRelease::new_from` and the other functions of `OsRelease` remain for the default options.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OsReleaseParser {
+    options: ParseOptions,
+}
+
+impl OsReleaseParser {
+    /// Creates a parser with `options`.
+    pub fn new(options: ParseOptions) -> OsReleaseParser {
+        OsReleaseParser { options }
+    }
+
+    /// Creates a builder of parser, starting from the default options.
+    pub fn builder() -> OsReleaseParserBuilder {
+        OsReleaseParserBuilder::default()
+    }
+
+    /// Returns the options of the parser.
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Parse the content of an os-release file. See `OsRelease::parse_with_options`.
+    pub fn parse_str(&self, content: &str) -> Result<OsRelease, OsReleaseError> {
+        OsRelease::parse_with_options(content, &self.options)
+    }
+
+    /// Parse an os-release file from a reader.
+    /// Returns `OsReleaseError::Io` if it can't be read, and `OsReleaseError::InvalidUtf8` if it isn't valid UTF-8.
+    pub fn parse_reader<R: Read>(&self, mut reader: R) -> Result<OsRelease, OsReleaseError> {
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+        self.parse_str(std::str::from_utf8(&content).map_err(OsReleaseError::InvalidUtf8)?)
+    }
+
+    /// Parse the os-release file at `path`.
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<OsRelease, OsReleaseError> {
+        self.parse_reader(open(&path)?)
+    }
+}
+
+/// Builder of `OsReleaseParser`, with a chainable setter for each field of `ParseOptions`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OsReleaseParserBuilder {
+    options: ParseOptions,
+}
+
+impl OsReleaseParserBuilder {
+    /// Sets `ParseOptions::mode`.
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Sets `ParseOptions::trim_unquoted`.
+    pub fn trim_unquoted(mut self, trim_unquoted: bool) -> Self {
+        self.options.trim_unquoted = trim_unquoted;
+        self
+    }
+
+    /// Sets `ParseOptions::strip_bom`.
+    pub fn strip_bom(mut self, strip_bom: bool) -> Self {
+        self.options.strip_bom = strip_bom;
+        self
+    }
+
+    /// Sets `ParseOptions::allow_export`.
+    pub fn allow_export(mut self, allow_export: bool) -> Self {
+        self.options.allow_export = allow_export;
+        self
+    }
+
+    /// Sets `ParseOptions::lenient_keys`.
+    pub fn lenient_keys(mut self, lenient_keys: bool) -> Self {
+        self.options.lenient_keys = lenient_keys;
+        self
+    }
+
+    /// Sets `ParseOptions::include_standard_in_extra`.
+    pub fn include_standard_in_extra(mut self, include_standard_in_extra: bool) -> Self {
+        self.options.include_standard_in_extra = include_standard_in_extra;
+        self
+    }
+
+    /// Sets `ParseOptions::comment_chars`.
+    pub fn comment_chars(mut self, comment_chars: Vec<char>) -> Self {
+        self.options.comment_chars = comment_chars;
+        self
+    }
+
+    /// Sets `ParseOptions::raw_values`.
+    pub fn raw_values(mut self, raw_values: bool) -> Self {
+        self.options.raw_values = raw_values;
+        self
+    }
+
+    /// Sets `ParseOptions::numeric_escapes`.
+    pub fn numeric_escapes(mut self, numeric_escapes: bool) -> Self {
+        self.options.numeric_escapes = numeric_escapes;
+        self
+    }
+
+    /// Returns the built `OsReleaseParser`.
+    pub fn build(self) -> OsReleaseParser {
+        OsReleaseParser::new(self.options)
+    }
+}
+
+impl FromIterator<String> for OsRelease {
+    /// Parse the lines of the `/etc/os-release` file.
+    /// The lines are expected to be in the form of `<key> = <value>`.
+    /// If keys aren't in the list of standard keys, there will be in `extra` field.
+    /// See the `OsRelease` struct for the list of standard keys.
+    /// Lines are parsed like `OsRelease::parse_with_options` with the default `ParseOptions`,
+    /// except that malformed values like spaces in `ID` are left as-is.
+    fn from_iter<I: IntoIterator<Item = String>>(lines: I) -> Self {
+        let mut os_release = Self::default();
+        os_release.parse_lines(lines, &ParseOptions::default(), |_, _| ()).expect("lenient parsing can't fail");
+        os_release
+    }
+}
+
+/// Read the lines of `reader`, skipping those which aren't valid UTF-8.
+/// Unlike `BufRead::lines`, an invalid line doesn't end the iteration,
+/// while errors of the reader itself are still returned.
+fn read_lines<R: BufRead>(reader: R) -> io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for line in reader.split(b'\n') {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        if let Ok(line) = String::from_utf8(line) {
+            lines.push(line);
+        }
+    }
+    Ok(lines)
+}
+
+/// Maximum number of symlinks followed by `symlink_error`, like `MAXSYMLINKS` of Linux.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Returns the error describing why the symlink at `path` can't be followed,
+/// or `None` if `path` isn't a symlink or if its chain can be followed.
+/// The chain is followed link by link; like the kernel, a chain of more than 40 links is a loop.
+fn symlink_error(path: &Path) -> Option<OsReleaseError> {
+    let target = std::fs::read_link(path).ok()?;
+    let mut current = path.to_path_buf();
+    for _ in 0..=MAX_SYMLINK_HOPS {
+        match std::fs::read_link(&current) {
+            Ok(next) => current = current.parent().map_or(next.clone(), |parent| parent.join(next)),
+            Err(_) if std::fs::symlink_metadata(&current).is_err() => {
+                return Some(OsReleaseError::BrokenSymlink { path: path.to_path_buf(), target });
+            }
+            Err(_) => return None,
+        }
+    }
+    Some(OsReleaseError::SymlinkLoop { path: path.to_path_buf(), target })
+}
+
+/// Open the file at the given path.
+/// If the file does not exist, return an error.
+fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    File::open(&path).map_err(|why| io::Error::new(
+        why.kind(),
+        format!("unable to open file at {:?}: {}", path.as_ref(), why)
+    ))
+}
+
+/// Read the JSON document at `path` of an OCI image layout.
+#[cfg(feature = "oci")]
+fn oci_json(path: &Path) -> io::Result<serde_json::Value> {
+    serde_json::from_reader(BufReader::new(open(path)?)).map_err(io::Error::from)
+}
+
+/// Returns the descriptors listed in the `manifests` array of an OCI index.
+#[cfg(feature = "oci")]
+fn oci_descriptors(index: &serde_json::Value) -> impl Iterator<Item = &serde_json::Value> {
+    index["manifests"].as_array().into_iter().flatten()
+}
+
+/// Returns the platform of an OCI descriptor as `os/architecture[/variant]`, like "linux/arm64/v8".
+#[cfg(feature = "oci")]
+fn oci_platform(descriptor: &serde_json::Value) -> String {
+    let platform = &descriptor["platform"];
+    let mut parts = vec![
+        platform["os"].as_str().unwrap_or_default(),
+        platform["architecture"].as_str().unwrap_or_default(),
+    ];
+    if let Some(variant) = platform["variant"].as_str() {
+        parts.push(variant);
+    }
+    parts.join("/")
+}
+
+/// Returns the path of the blob of an OCI descriptor, like `blobs/sha256/<hex>`.
+#[cfg(feature = "oci")]
+fn oci_blob_path(layout_dir: &Path, descriptor: &serde_json::Value) -> io::Result<std::path::PathBuf> {
+    descriptor["digest"]
+        .as_str()
+        .and_then(|digest| digest.split_once(':'))
+        .filter(|(algorithm, hex)| !algorithm.contains(['/', '.']) && !hex.contains(['/', '.']))
+        .map(|(algorithm, hex)| layout_dir.join("blobs").join(algorithm).join(hex))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid digest in OCI descriptor"))
+}
+
+/// Parse the os-release file of a tar archive in a single pass over its entries,
+/// preferring `etc/os-release` over `usr/lib/os-release`.
+/// The boolean is `true` if the file is `etc/os-release`.
+#[cfg(all(feature = "tar", feature = "gzip"))]
+fn tar_os_release<R: Read>(archive: &mut tar::Archive<R>) -> io::Result<Option<(OsRelease, bool)>> {
+    let mut fallback = None;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        match path.trim_start_matches("./").trim_start_matches('/') {
+            "etc/os-release" => return Ok(Some((OsRelease::from_reader(entry)?, true))),
+            "usr/lib/os-release" => fallback = Some((OsRelease::from_reader(entry)?, false)),
+            _ => (),
+        }
+    }
+    Ok(fallback)
+}
+
+/// Reader recording whether the inner reader failed,
+/// to tell decompression errors from errors of the decompressed format.
+#[cfg(all(feature = "tar", feature = "gzip"))]
+struct ReadFailures<R> {
+    inner:  R,
+    failed: bool,
+}
+
+#[cfg(all(feature = "tar", feature = "gzip"))]
+impl<R> ReadFailures<R> {
+    fn new(inner: R) -> Self {
+        ReadFailures { inner, failed: false }
+    }
+}
+
+#[cfg(all(feature = "tar", feature = "gzip"))]
+impl<R: Read> Read for ReadFailures<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf).inspect_err(|_| self.failed = true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"NAME="Arch Linux"
+PRETTY_NAME="Arch Linux"
+ID=arch
+BUILD_ID=rolling
+ANSI_COLOR="38;2;23;147;209"
+HOME_URL="https://archlinux.org/"
+DOCUMENTATION_URL="https://wiki.archlinux.org/"
+SUPPORT_URL="https://archlinux.org/"
+BUG_REPORT_URL="https://bugs.archlinux.org/"
+LOGO=archlinux-logo
+EXTRA_KEY=thing"#;
+
+    #[test]
+    fn os_release() {
+        let os_release = OsRelease::from_iter(EXAMPLE.lines().map(|x| x.into()));
+
+        assert_eq!(
+            os_release,
+            OsRelease {
+                name:               "Arch Linux".into(),
+                pretty_name:        "Arch Linux".into(),
+                version:            "".into(),
+                id:                 "arch".into(),
+                id_like:            "".into(),
+                image_id:           "".into(),
+                variant_id:         "".into(),
+                version_id:         "".into(),
+                home_url:           "https://archlinux.org/".into(),
+                support_end:        "".into(),
+                support_url:        "https://archlinux.org/".into(),
+                bug_report_url:     "https://bugs.archlinux.org/".into(),
+                privacy_policy_url: "".into(),
+                version_codename:   "".into(),
+                logo:               "archlinux-logo".into(),
+                build_id:           "rolling".into(),
+                ansi_color:         "38;2;23;147;209".into(),
+                documentation_url:   "https://wiki.archlinux.org/".into(),
+                extra: {
+                    let mut map = BTreeMap::new();
+                    map.insert("EXTRA_KEY".to_owned(), "thing".to_owned());
+                    map
+                }
+            }
+        )
+    }
+
+    #[test]
+    fn new_from_invalid_utf8() {
+        let path = std::env::temp_dir().join(format!("os-release-rs-invalid-utf8-{}", std::process::id()));
+        std::fs::write(&path, b"NAME=\"Caf\xe9 Linux\"\r\nID=cafe\nVERSION_ID=1").unwrap();
+        let os_release = OsRelease::new_from(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let os_release = os_release.unwrap();
+        assert_eq!(os_release.name, "");
+        assert_eq!(os_release.id, "cafe");
+        assert_eq!(os_release.version_id, "1");
+
+        let missing = OsRelease::new_from("/nonexistent/os-release").unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        assert!(OsRelease::new_from(std::env::temp_dir()).is_err());
+    }
+
+    const ALMALINUX: &str = r#"NAME="AlmaLinux"
+VERSION="9.3 (Shamrock Pampas Cat)"
+ID="almalinux"
+ID_LIKE="rhel centos fedora"
+VERSION_ID="9.3"
+PRETTY_NAME="AlmaLinux 9.3 (Shamrock Pampas Cat)""#;
+
+    const ROCKY: &str = r#"NAME="Rocky Linux"
+VERSION="9.3 (Blue Onyx)"
+ID="rocky"
+ID_LIKE="rhel centos fedora"
+VERSION_ID="9.3"
+PRETTY_NAME="Rocky Linux 9.3 (Blue Onyx)""#;
+
+    const FEDORA: &str = r#"NAME="Fedora Linux"
+VERSION="39 (Workstation Edition)"
+ID=fedora
+VERSION_ID=39
+VERSION_CODENAME=""
+PRETTY_NAME="Fedora Linux 39 (Workstation Edition)"
+HOME_URL="https://fedoraproject.org/""#;
+
+    const UBUNTU: &str = r#"PRETTY_NAME="Ubuntu 22.04.3 LTS"
+NAME="Ubuntu"
+VERSION_ID="22.04"
+VERSION="22.04.3 LTS (Jammy Jellyfish)"
+VERSION_CODENAME=jammy
+ID=ubuntu
+ID_LIKE=debian
+HOME_URL="https://www.ubuntu.com/"
+SUPPORT_URL="https://help.ubuntu.com/"
+BUG_REPORT_URL="https://bugs.launchpad.net/ubuntu/"
+PRIVACY_POLICY_URL="https://www.ubuntu.com/legal/terms-and-policies/privacy-policy"
+UBUNTU_CODENAME=jammy"#;
+
+    fn parse(content: &str) -> OsRelease {
+        OsRelease::from_iter(content.lines().map(|x| x.into()))
+    }
+
+    /// Write `content` to a file unique to this test in the temporary directory.
+    fn temp_file(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("os-release-rs-{}-{}", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn is_enterprise_linux() {
+        assert!(parse(ALMALINUX).is_enterprise_linux());
+        assert!(parse(ROCKY).is_enterprise_linux());
+        assert!(!parse(FEDORA).is_enterprise_linux());
+        assert!(!parse(UBUNTU).is_enterprise_linux());
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn semver() {
+        let mut os_release = OsRelease { version_id: "3.18.4".into(), ..Default::default() };
+        assert_eq!(os_release.semver(), Some(semver::Version::new(3, 18, 4)));
+
+        os_release.version_id = "22.04".into();
+        assert_eq!(os_release.semver(), None);
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn json_schema_string() {
+        let schema = OsRelease::json_schema_string();
+        assert!(schema.contains(r#""name":"#));
+        assert!(schema.contains(r#""extra":"#));
+    }
+
+    #[test]
+    fn quoted_extra() {
+        let os_release = parse("EXTRA_KEY=\"Key=Value Edition\"\nOTHER_KEY='single'");
+        assert_eq!(os_release.extra["EXTRA_KEY"], "Key=Value Edition");
+        assert_eq!(os_release.extra["OTHER_KEY"], "single");
+    }
+
+    #[test]
+    fn parse_with_mode_id_with_spaces() {
+        let content = "NAME=\"Arch Linux\"\nID=\"arch linux\"";
+
+        let (os_release, warnings) = OsRelease::parse_with_mode(content, ParseMode::Lenient).unwrap();
+        assert_eq!(os_release.id, "arch-linux");
+        assert_eq!(warnings.len(), 1);
+
+        match OsRelease::parse_with_mode(content, ParseMode::Strict) {
+            Err(OsReleaseError::InvalidId(id)) => assert_eq!(id, "arch linux"),
+            other => panic!("expected InvalidId, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_with_source() {
+        let (os_release, lines) = OsRelease::parse_with_source(EXAMPLE);
+        assert_eq!(os_release, parse(EXAMPLE));
+        assert_eq!(lines, EXAMPLE.split('\n').collect::<Vec<_>>());
+
+        let content = "NAME=\"Arch Linux\"\r\nID=arch\r\n";
+        let (os_release, lines) = OsRelease::parse_with_source(content);
+        assert_eq!(os_release, parse("NAME=\"Arch Linux\"\nID=arch"));
+        assert_eq!(lines, vec!["NAME=\"Arch Linux\"\r", "ID=arch\r", ""]);
+        assert_eq!(lines.join("\n"), content);
+    }
+
+    #[test]
+    fn eq_identity() {
+        let os_release = parse(EXAMPLE);
+        let mut other = os_release.clone();
+        other.home_url = "https://example.com/".into();
+        assert_ne!(os_release, other);
+        assert!(os_release.eq_identity(&other));
+
+        other.variant_id = "server".into();
+        assert!(!os_release.eq_identity(&other));
+    }
+
+    #[test]
+    fn is_atomic() {
+        let silverblue = parse(r#"NAME="Fedora Linux"
+VERSION="39.20231204.0 (Silverblue)"
+ID=fedora
+VERSION_ID=39
+PRETTY_NAME="Fedora Linux 39.20231204.0 (Silverblue)"
+VARIANT="Silverblue"
+VARIANT_ID=silverblue"#);
+        assert!(silverblue.is_atomic());
+        assert!(!parse(FEDORA).is_atomic());
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn from_zip() {
+        use std::io::{Cursor, Write};
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("usr/lib/os-release", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(EXAMPLE.as_bytes()).unwrap();
+        let archive = writer.finish().unwrap();
+        assert_eq!(OsRelease::from_zip(archive).unwrap(), Some(parse(EXAMPLE)));
+
+        let writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let archive = writer.finish().unwrap();
+        assert_eq!(OsRelease::from_zip(archive).unwrap(), None);
+    }
+
+    #[test]
+    fn support_status() {
+        let mut os_release = OsRelease { support_end: "2024-11-12".into(), ..Default::default() };
+        assert_eq!(os_release.support_end_date(), Some((2024, 11, 12)));
+        assert_eq!(os_release.support_status((2024, 1, 1), 90), SupportStatus::Supported);
+        assert_eq!(os_release.support_status((2024, 11, 2), 90), SupportStatus::EndingSoon(10));
+        assert_eq!(os_release.support_status((2025, 1, 1), 90), SupportStatus::Ended(50));
+
+        os_release.support_end = "".into();
+        assert_eq!(os_release.support_status((2024, 1, 1), 90), SupportStatus::Unknown);
+        os_release.support_end = "soon".into();
+        assert_eq!(os_release.support_status((2024, 1, 1), 90), SupportStatus::Unknown);
+
+        os_release.support_end = "2024-02-30".into();
+        assert_eq!(os_release.support_end_date(), None);
+        os_release.support_end = "2023-02-29".into();
+        assert_eq!(os_release.support_end_date(), None);
+        os_release.support_end = "2024-02-29".into();
+        assert_eq!(os_release.support_end_date(), Some((2024, 2, 29)));
+        os_release.support_end = "2100-02-29".into();
+        assert_eq!(os_release.support_end_date(), None);
+        os_release.support_end = "2024-04-31".into();
+        assert_eq!(os_release.support_end_date(), None);
+    }
+
+    #[test]
+    fn version_satisfies() {
+        let ubuntu = parse(UBUNTU);
+        assert!(ubuntu.version_satisfies(">=22.04"));
+        assert!(!ubuntu.version_satisfies(">=22.10"));
+        assert!(ubuntu.version_satisfies("<23"));
+        assert!(!ubuntu.version_satisfies("<22"));
+        assert!(ubuntu.version_satisfies("22.*"));
+        assert!(!ubuntu.version_satisfies(">=twenty"));
+
+        let rocky = parse(ROCKY);
+        assert!(rocky.version_satisfies("9.*"));
+        assert!(!rocky.version_satisfies("8.*"));
+        assert!(rocky.version_satisfies("=9.3"));
+
+        assert!(!parse(EXAMPLE).version_satisfies(">=1"));
+    }
+
+    #[test]
+    fn from_layers() {
+        let etc = "PRETTY_NAME=\"Etc\"";
+        let run = "PRETTY_NAME=\"Run\"\nVARIANT_ID=run";
+        let usr = "PRETTY_NAME=\"Usr\"\nVARIANT_ID=usr\nID=arch";
+
+        let os_release = OsRelease::from_layers([etc, run, usr]);
+        assert_eq!(os_release.pretty_name, "Etc");
+        assert_eq!(os_release.variant_id, "run");
+        assert_eq!(os_release.id, "arch");
+
+        let os_release = OsRelease::from_layers(["\u{feff}NAME=Etc", "NAME=Usr"]);
+        assert_eq!(os_release.name, "Etc");
+        assert!(os_release.extra.is_empty());
+    }
+
+    #[test]
+    fn new_from_layers_missing() {
+        assert!(OsRelease::new_from_layers(&["/nonexistent/os-release"]).is_err());
+
+        let path = std::env::temp_dir().join(format!("os-release-rs-layers-utf8-{}", std::process::id()));
+        std::fs::write(&path, b"NAME=\"Caf\xe9 Linux\"\nID=cafe\n").unwrap();
+        let os_release = OsRelease::new_from_layers(&[Path::new("/nonexistent/os-release"), &path]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(os_release.unwrap().id, "cafe");
+
+        assert!(OsRelease::new_from_layers(&[std::env::temp_dir(), path]).is_err());
+    }
+
+    #[test]
+    fn parse_with_spans() {
+        let (os_release, spans) = OsRelease::parse_with_spans(UBUNTU);
+        assert_eq!(os_release, parse(UBUNTU));
+        assert_eq!(spans["PRETTY_NAME"], 1);
+        assert_eq!(spans["NAME"], 2);
+        assert_eq!(spans["UBUNTU_CODENAME"], 12);
+
+        let (_, spans) = OsRelease::parse_with_spans("# NAME=comment\nNAME=first\nID=arch\nNAME=second");
+        assert_eq!(spans["NAME"], 4);
+
+        let (os_release, spans) = OsRelease::parse_with_spans("FOO=kept\nFOO=\nlower=1\nEMPTY=\n  # COMMENT=1");
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans["FOO"], 1);
+        assert_eq!(spans["lower"], 3);
+        assert!(spans.keys().all(|key| os_release.extra.contains_key(key)));
+    }
+
+    #[test]
+    fn diff_from_baseline() {
+        let mut baseline = BTreeMap::new();
+        baseline.insert("ID".to_owned(), "arch".to_owned());
+        baseline.insert("BUILD_ID".to_owned(), "2024.01.01".to_owned());
+        baseline.insert("VERSION_ID".to_owned(), "1".to_owned());
+        baseline.insert("EXTRA_KEY".to_owned(), "thing".to_owned());
+
+        assert_eq!(
+            parse(EXAMPLE).diff_from_baseline(&baseline),
+            vec![
+                ("BUILD_ID".to_owned(), Some("rolling".to_owned()), "2024.01.01".to_owned()),
+                ("VERSION_ID".to_owned(), None, "1".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_iter_prefixed() {
+        let content = "GUEST_NAME=\"Arch Linux\"\nGUEST_ID=arch\nGUEST_EXTRA_KEY=thing\nID=debian";
+        let os_release = OsRelease::from_iter_prefixed(content.lines().map(String::from), "GUEST_");
+        assert_eq!(os_release.name, "Arch Linux");
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.extra["EXTRA_KEY"], "thing");
+        assert_eq!(os_release.extra["ID"], "debian");
+    }
+
+    #[test]
+    fn is_ubuntu_lts() {
+        assert!(parse(UBUNTU).is_ubuntu_lts());
+        assert!(parse("ID=ubuntu\nVERSION_ID=\"24.04\"").is_ubuntu_lts());
+
+        let mantic = parse("ID=ubuntu\nVERSION_ID=\"23.10\"\nVERSION=\"23.10 (Mantic Minotaur)\"");
+        assert!(!mantic.is_ubuntu_lts());
+        assert!(!parse("ID=ubuntu\nVERSION_ID=\"23.04\"").is_ubuntu_lts());
+        assert!(!parse(FEDORA).is_ubuntu_lts());
+    }
+
+    #[test]
+    fn lineage() {
+        assert_eq!(parse(UBUNTU).lineage(), vec!["ubuntu", "debian"]);
+        assert_eq!(parse(EXAMPLE).lineage(), vec!["arch"]);
+        assert_eq!(parse(ROCKY).lineage(), vec!["rocky", "rhel", "centos", "fedora"]);
+        assert_eq!(parse("ID_LIKE=\"debian debian\"").lineage(), vec!["debian"]);
+    }
+
+    #[test]
+    fn from_iter_ci_extras() {
+        let lines = || vec!["FOO=1".to_owned(), "foo=2".to_owned()];
+
+        let os_release = OsRelease::from_iter_ci_extras(lines());
+        assert_eq!(os_release.extra.len(), 1);
+        assert_eq!(os_release.extra["FOO"], "2");
+
+        let os_release = OsRelease::from_iter(lines());
+        assert_eq!(os_release.extra.len(), 2);
+
+        let os_release = OsRelease::from_iter_ci_extras(vec!["name=Foo".to_owned(), "Id=foo".to_owned()]);
+        assert_eq!(os_release.name, "Foo");
+        assert_eq!(os_release.id, "foo");
+        assert!(os_release.extra.is_empty());
+    }
+
+    #[test]
+    fn pretty_name_consistent() {
+        assert!(parse(UBUNTU).pretty_name_consistent());
+        assert!(parse(EXAMPLE).pretty_name_consistent());
+        assert!(!parse("NAME=\"Fedora Linux\"\nPRETTY_NAME=\"Arch Linux\"").pretty_name_consistent());
+    }
+
+    #[test]
+    fn expand_variables() {
+        let mut os_release = parse("NAME=Arch\nVERSION=rolling\nPRETTY_NAME=\"${NAME} ${VERSION} ${MISSING}\"\nFOO=\"${NAME}-foo\"");
+        os_release.expand_variables();
+        assert_eq!(os_release.pretty_name, "Arch rolling ${MISSING}");
+        assert_eq!(os_release.extra["FOO"], "Arch-foo");
+    }
+
+    #[test]
+    fn expand_variables_limit() {
+        let mut content: Vec<String> = (0..40).map(|i| format!("KEY_{}=\"${{KEY_{}}}\"", i, i + 1)).collect();
+        content.push("KEY_40=end".to_owned());
+        content.push("LOOP=\"${LOOP}\"".to_owned());
+
+        let mut os_release = OsRelease::from_iter(content);
+        os_release.expand_variables_with_limit(4);
+        assert_eq!(os_release.extra["KEY_0"], "${KEY_16}");
+        assert_eq!(os_release.extra["KEY_25"], "end");
+        assert_eq!(os_release.extra["LOOP"], "${LOOP}");
+
+        os_release.expand_variables();
+        assert_eq!(os_release.extra["KEY_0"], "end");
+        assert_eq!(os_release.extra["LOOP"], "${LOOP}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn new_from_timeout() {
+        let fifo = std::env::temp_dir().join(format!("os-release-rs-fifo-{}", std::process::id()));
+        let status = std::process::Command::new("mkfifo").arg(&fifo).status().unwrap();
+        assert!(status.success());
+
+        let result = OsRelease::new_from_timeout(&fifo, Duration::from_millis(100));
+        std::fs::remove_file(&fifo).unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn to_export_script() {
+        let script = parse(EXAMPLE).to_export_script();
+        assert!(script.lines().any(|line| line == r#"export NAME="Arch Linux""#));
+        assert!(script.lines().any(|line| line == r#"export EXTRA_KEY="thing""#));
+        assert!(!script.contains("VERSION_ID"));
+
+        let os_release = OsRelease { name: r#"My "$HOME" OS"#.into(), ..Default::default() };
+        assert_eq!(os_release.to_export_script(), "export NAME=\"My \\\"\\$HOME\\\" OS\"\n");
+    }
+
+    #[test]
+    fn merge_with() {
+        let mut os_release = parse("NAME=Arch\nID=arch\nFOO=\"a longer value\"\nBAR=short");
+        let other = parse("NAME=\"Arch Linux\"\nVERSION_ID=1\nFOO=value\nBAR=longer");
+
+        os_release.merge_with(&other, |_, mine, theirs| {
+            if theirs.len() > mine.len() { theirs.to_owned() } else { mine.to_owned() }
+        });
+        assert_eq!(os_release.name, "Arch Linux");
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.version_id, "1");
+        assert_eq!(os_release.extra["FOO"], "a longer value");
+        assert_eq!(os_release.extra["BAR"], "longer");
+    }
+
+    #[test]
+    fn parse_with_mode_unterminated_quote() {
+        let content = "ID=arch\nNAME=\"Foo";
+
+        let (os_release, _) = OsRelease::parse_with_mode(content, ParseMode::Lenient).unwrap();
+        assert_eq!(os_release.name, "\"Foo");
+
+        match OsRelease::parse_with_mode(content, ParseMode::Strict) {
+            Err(OsReleaseError::UnterminatedQuote { line, content }) => {
+                assert_eq!(line, 2);
+                assert_eq!(content, "NAME=\"Foo");
+            }
+            other => panic!("expected UnterminatedQuote, got {:?}", other),
+        }
+        assert!(OsRelease::parse_with_mode("NAME=\"\"", ParseMode::Strict).is_ok());
+    }
+
+    #[test]
+    fn build_kind() {
+        let build_kind = |build_id: &str| OsRelease { build_id: build_id.into(), ..Default::default() }.build_kind();
+        assert_eq!(build_kind("rolling"), BuildKind::Rolling);
+        assert_eq!(build_kind("2024-01-15"), BuildKind::Dated("2024-01-15".into()));
+        assert_eq!(build_kind("3f2a9c1d"), BuildKind::Commit("3f2a9c1d".into()));
+        assert_eq!(build_kind("nightly"), BuildKind::Other("nightly".into()));
+        assert_eq!(build_kind("20240115"), BuildKind::Other("20240115".into()));
+        assert_eq!(build_kind("1234567"), BuildKind::Other("1234567".into()));
+        assert_eq!(build_kind(""), BuildKind::None);
+    }
+
+    #[test]
+    fn into_extra() {
+        let map = parse(EXAMPLE).into_extra();
+        let keys: Vec<&str> = map.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec![
+            "ANSI_COLOR", "BUG_REPORT_URL", "BUILD_ID", "DOCUMENTATION_URL", "EXTRA_KEY",
+            "HOME_URL", "ID", "LOGO", "NAME", "PRETTY_NAME", "SUPPORT_URL",
+        ]);
+        assert_eq!(map["NAME"], "Arch Linux");
+        assert_eq!(map["EXTRA_KEY"], "thing");
+    }
+
+    #[test]
+    fn parse_with_options() {
+        let content = "\u{feff}NAME=  Arch  \nexport ID=arch\nlowercase=key\n# COMMENT=1";
+
+        let os_release = OsRelease::parse_with_options(content, &ParseOptions::default()).unwrap();
+        assert_eq!(os_release.name, "Arch");
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.extra.len(), 1);
+        assert_eq!(os_release.extra["lowercase"], "key");
+
+        let options = ParseOptions { trim_unquoted: false, lenient_keys: false, ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(content, &options).unwrap();
+        assert_eq!(os_release.name, "  Arch  ");
+        assert!(os_release.extra.is_empty());
+
+        let options = ParseOptions { strip_bom: false, allow_export: false, ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(content, &options).unwrap();
+        assert_eq!(os_release.name, "");
+        assert_eq!(os_release.id, "");
+        assert_eq!(os_release.extra["\u{feff}NAME"], "Arch");
+        assert_eq!(os_release.extra["export ID"], "arch");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn to_toml() {
+        let toml = parse(EXAMPLE).to_toml();
+        assert!(toml.lines().any(|line| line == r#"id = "arch""#));
+        assert!(toml.lines().any(|line| line == r#"name = "Arch Linux""#));
+        assert!(toml.contains("[extra]\nEXTRA_KEY = \"thing\""));
+        assert!(!toml.contains("version_id"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_raw_fd() {
+        use std::os::unix::io::IntoRawFd;
+
+        let path = temp_file("from-raw-fd", EXAMPLE);
+        let fd = File::open(&path).unwrap().into_raw_fd();
+        let os_release = unsafe { OsRelease::from_raw_fd(fd) }.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(os_release, parse(EXAMPLE));
+
+        let fd = File::open(std::env::temp_dir()).unwrap().into_raw_fd();
+        assert!(unsafe { OsRelease::from_raw_fd(fd) }.is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn init_system_at() {
+        let root = std::env::temp_dir().join(format!("os-release-rs-init-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        assert_eq!(OsRelease::init_system_at(&root), InitSystem::Unknown);
+
+        std::fs::create_dir_all(root.join("run/systemd/system")).unwrap();
+        assert_eq!(OsRelease::init_system_at(&root), InitSystem::Systemd);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn build_id_consistent() {
+        assert!(parse(EXAMPLE).build_id_consistent());
+        assert!(parse(UBUNTU).build_id_consistent());
+        assert!(!parse("ID=arch\nBUILD_ID=rolling\nVERSION_ID=2024.01").build_id_consistent());
+    }
+
+    #[test]
+    fn version_info() {
+        assert_eq!(
+            parse(UBUNTU).version_info(),
+            VersionInfo {
+                version:          "22.04.3 LTS (Jammy Jellyfish)".into(),
+                version_id:       "22.04".into(),
+                version_codename: "jammy".into(),
+                numeric:          Some((22, 4, 0)),
+                codename:         Some("jammy".into()),
+            }
+        );
+
+        let rocky = parse(ROCKY).version_info();
+        assert_eq!(rocky.numeric, Some((9, 3, 0)));
+        assert_eq!(rocky.codename, Some("Blue Onyx".into()));
+
+        let arch = parse(EXAMPLE).version_info();
+        assert_eq!(arch.numeric, None);
+        assert_eq!(arch.codename, None);
+    }
+
+    #[test]
+    fn from_bytes_with_encoding() {
+        let utf8 = "NAME=\"Dérivée\"".as_bytes();
+        let latin1 = b"NAME=\"D\xe9riv\xe9e\"";
+
+        let os_release = OsRelease::from_bytes_with_encoding(utf8, Encoding::Utf8Strict).unwrap();
+        assert_eq!(os_release.name, "Dérivée");
+        let os_release = OsRelease::from_bytes_with_encoding(latin1, Encoding::Latin1).unwrap();
+        assert_eq!(os_release.name, "Dérivée");
+        let os_release = OsRelease::from_bytes_with_encoding(latin1, Encoding::Utf8Lossy).unwrap();
+        assert_eq!(os_release.name, "D\u{fffd}riv\u{fffd}e");
+
+        match OsRelease::from_bytes_with_encoding(latin1, Encoding::Utf8Strict) {
+            Err(OsReleaseError::InvalidUtf8(_)) => {}
+            other => panic!("expected InvalidUtf8, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn is_upgrade_from() {
+        let jammy = parse(UBUNTU);
+        let noble = parse("ID=Ubuntu\nVERSION_ID=\"24.04\"");
+        assert!(noble.is_upgrade_from(&jammy));
+        assert!(!jammy.is_upgrade_from(&noble));
+        assert!(!jammy.is_upgrade_from(&jammy));
+        assert!(!parse(FEDORA).is_upgrade_from(&jammy));
+
+        let arch = parse(EXAMPLE);
+        assert!(arch.is_upgrade_from(&arch.clone()));
+    }
+
+    #[test]
+    fn all_urls() {
+        let mut os_release = parse(EXAMPLE);
+        os_release.version = "rolling (see https://archlinux.org/news/)".into();
+        os_release.extra.insert("MIRROR".into(), "main: https://geo.mirror.pkgbuild.com/ backup: http://mirror.example.org".into());
+
+        assert_eq!(os_release.all_urls(), vec![
+            "https://bugs.archlinux.org/",
+            "https://wiki.archlinux.org/",
+            "https://archlinux.org/",
+            "https://archlinux.org/news/",
+            "https://geo.mirror.pkgbuild.com/",
+            "http://mirror.example.org",
+        ]);
+    }
+
+    #[test]
+    fn builder_from_paths() {
+        let path = temp_file("builder", EXAMPLE);
+        let os_release = OsReleaseBuilder::from_paths(&[&path]).unwrap()
+            .pretty_name("My Arch Linux")
+            .extra("EXTRA_KEY", "other")
+            .build();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(os_release.pretty_name, "My Arch Linux");
+        assert_eq!(os_release.name, "Arch Linux");
+        assert_eq!(os_release.extra["EXTRA_KEY"], "other");
+        assert!(OsReleaseBuilder::from_paths(&["/nonexistent/os-release"]).is_err());
+    }
+
+    #[test]
+    fn is_compatible_with() {
+        let pop = parse("NAME=\"Pop!_OS\"\nID=pop\nID_LIKE=\"ubuntu debian\"");
+        assert!(pop.is_compatible_with(&["debian"]));
+        assert!(pop.is_compatible_with(&["pop", "fedora"]));
+        assert!(!pop.is_compatible_with(&["fedora", "arch"]));
+        assert!(!OsRelease::default().is_compatible_with(&[""]));
+    }
+
+    #[test]
+    fn include_standard_in_extra() {
+        let os_release = OsRelease::parse_with_options(EXAMPLE, &ParseOptions::default()).unwrap();
+        assert!(!os_release.extra.contains_key("NAME"));
+
+        let options = ParseOptions { include_standard_in_extra: true, ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(EXAMPLE, &options).unwrap();
+        assert_eq!(os_release.name, "Arch Linux");
+        assert_eq!(os_release.extra["NAME"], "Arch Linux");
+        assert_eq!(os_release.extra["EXTRA_KEY"], "thing");
+
+        let os_release = OsRelease::parse_with_options("VERSION=\nFOO=\"\"", &options).unwrap();
+        assert!(os_release.extra.is_empty());
+    }
+
+    #[test]
+    fn parse_with_options_matches_from_iter() {
+        let options = ParseOptions::default();
+        for content in [EXAMPLE, ALMALINUX, ROCKY, FEDORA, UBUNTU] {
+            assert_eq!(OsRelease::parse_with_options(content, &options).unwrap(), parse(content));
+        }
+
+        let content = "\u{feff}NAME=\"\\x41rch\"\nexport ID=arch\nVERSION=\nFOO=\"\"\n  # COMMENT=1";
+        let os_release = parse(content);
+        assert_eq!(OsRelease::parse_with_options(content, &options).unwrap(), os_release);
+        assert_eq!(os_release.name, "\\x41rch");
+        assert_eq!(os_release.id, "arch");
+        assert!(os_release.extra.is_empty());
+    }
+
+    #[test]
+    fn update_from_lines() {
+        let mut os_release = parse("ID=arch\nNAME=Arch");
+        os_release.update_from_lines(vec!["VERSION_ID=1".to_owned(), "NAME=\"Arch Linux\"".to_owned()]);
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.version_id, "1");
+        assert_eq!(os_release.name, "Arch Linux");
+    }
+
+    #[test]
+    fn canonical_bytes() {
+        let a = parse("NAME=\"Arch Linux\"\nID=arch\nEXTRA_KEY=thing\nVERSION_ID=");
+        let b = parse("# Comment\nEXTRA_KEY='thing'\n  ID=\"arch\"  \nNAME='Arch Linux'");
+        assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+        assert_eq!(a.canonical_bytes(), b"EXTRA_KEY=\"thing\"\nID=\"arch\"\nNAME=\"Arch Linux\"\n".to_vec());
+    }
+
+    #[test]
+    fn systemd_extras() {
+        let os_release = parse("ID=arch\nSYSTEMD_FOO=bar\nVENDOR_FOO=baz");
+        let mut expected = BTreeMap::new();
+        expected.insert("SYSTEMD_FOO", "bar");
+        assert_eq!(os_release.systemd_extras(), expected);
+    }
+
+    #[test]
+    fn recover_id() {
+        let mut os_release = parse("NAME=\"Arch Linux\"\nPRETTY_NAME=\"Arch Linux\"");
+        os_release.recover_id();
+        assert_eq!(os_release.id, "arch");
+
+        let mut os_release = parse("NAME=\"Pop!_OS\"");
+        os_release.recover_id();
+        assert_eq!(os_release.id, "popos");
+
+        let mut os_release = parse(UBUNTU);
+        os_release.recover_id();
+        assert_eq!(os_release.id, "ubuntu");
+    }
+
+    #[test]
+    fn new_from_root() {
+        let root = std::env::temp_dir().join(format!("os-release-rs-initrd-{}", std::process::id()));
+        let initramfs = root.join("run/initramfs");
+        assert_eq!(OsRelease::new_from_root(&initramfs).unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        std::fs::create_dir_all(initramfs.join("usr/lib")).unwrap();
+        std::fs::write(initramfs.join("usr/lib/os-release"), EXAMPLE).unwrap();
+        let os_release = OsRelease::new_from_root(&initramfs);
+
+        std::fs::create_dir_all(initramfs.join("etc")).unwrap();
+        std::fs::write(initramfs.join("etc/os-release"), "NAME=Initrd\nID=initrd").unwrap();
+        let etc = OsRelease::new_from_root(&initramfs);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(os_release.unwrap(), parse(EXAMPLE));
+        assert_eq!(etc.unwrap(), parse("NAME=Initrd\nID=initrd"));
+    }
+
+    #[test]
+    fn diff_pretty() {
+        let arch = parse(EXAMPLE);
+        let mut other = arch.clone();
+        other.id = "debian".into();
+        other.version_id = "12".into();
+        other.extra.clear();
+
+        assert_eq!(arch.diff(&other), vec![
+            ("EXTRA_KEY".to_owned(), Some("thing".to_owned()), None),
+            ("ID".to_owned(), Some("arch".to_owned()), Some("debian".to_owned())),
+            ("VERSION_ID".to_owned(), None, Some("12".to_owned())),
+        ]);
+        assert_eq!(arch.diff_pretty(&other), "- EXTRA_KEY=thing\n- ID=arch\n+ ID=debian\n+ VERSION_ID=12\n");
+        assert!(arch.diff_pretty(&arch).is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn from_metadata_json() {
+        let json = r#"{"os": {"release": {"ID": "debian", "NAME": "Debian GNU/Linux", "VERSION_ID": 12, "FOO": "bar"}}}"#;
+
+        let os_release = OsRelease::from_metadata_json(json, "/os/release").unwrap();
+        assert_eq!(os_release.id, "debian");
+        assert_eq!(os_release.name, "Debian GNU/Linux");
+        assert_eq!(os_release.version_id, "12");
+        assert_eq!(os_release.extra["FOO"], "bar");
+
+        match OsRelease::from_metadata_json(json, "/os/missing") {
+            Err(OsReleaseError::JsonPointer(pointer)) => assert_eq!(pointer, "/os/missing"),
+            other => panic!("expected JsonPointer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fields_exceeding() {
+        let mut os_release = parse(EXAMPLE);
+        os_release.pretty_name = "Arch Linux with a very long pretty name".into();
+        assert_eq!(os_release.fields_exceeding(24), vec![
+            (OsReleaseField::BugReportUrl, 27),
+            (OsReleaseField::DocumentationUrl, 27),
+            (OsReleaseField::PrettyName, 39),
+        ]);
+
+        os_release.pretty_name = "Dérivée".into();
+        assert_eq!(os_release.fields_exceeding(27), vec![]);
+        assert!(os_release.fields_exceeding(8).contains(&(OsReleaseField::PrettyName, 9)));
+    }
+
+    #[test]
+    fn require_keys() {
+        let arch = parse(EXAMPLE);
+        assert_eq!(arch.require_keys(&["ID", "VERSION_ID"]), Err(vec!["VERSION_ID".to_owned()]));
+        assert_eq!(arch.require_keys(&["ID", "EXTRA_KEY"]), Ok(()));
+    }
+
+    #[test]
+    fn logo_base() {
+        assert_eq!(parse(EXAMPLE).logo_base(), "archlinux");
+        assert_eq!(parse("LOGO=fedora_logo").logo_base(), "fedora_logo");
+    }
+
+    #[test]
+    fn comment_chars() {
+        let content = "; NAME=Commented\n# ID=hash\nNAME=\"Arch Linux\"";
+
+        let os_release = OsRelease::parse_with_options(content, &ParseOptions::default()).unwrap();
+        assert_eq!(os_release.name, "Arch Linux");
+        assert!(os_release.id.is_empty());
+        assert!(!os_release.extra.contains_key("# ID"));
+
+        let options = ParseOptions { comment_chars: vec!['#', ';'], ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(content, &options).unwrap();
+        assert_eq!(os_release.name, "Arch Linux");
+        assert!(os_release.extra.is_empty());
+    }
+
+    #[test]
+    fn from_iter_comments() {
+        let os_release = parse("# NAME=Commented\n   # ID=indented\n#\nNAME=\"Arch Linux\"\nEXTRA_KEY=\"# not a comment\"");
+        assert_eq!(os_release.name, "Arch Linux");
+        assert!(os_release.id.is_empty());
+        assert_eq!(os_release.extra.len(), 1);
+        assert_eq!(os_release.extra["EXTRA_KEY"], "# not a comment");
+    }
+
+    #[test]
+    fn is_prerelease() {
+        let rawhide = parse(r#"NAME="Fedora Linux"
+VERSION="41 (Workstation Edition Prerelease)"
+ID=fedora
+VERSION_ID=41
+VERSION_CODENAME=""
+PRETTY_NAME="Fedora Linux 41 (Workstation Edition Prerelease)"
+REDHAT_BUGZILLA_PRODUCT_VERSION=rawhide"#);
+        assert!(rawhide.is_prerelease());
+
+        assert!(parse("ID=debian\nVERSION_CODENAME=sid").is_prerelease());
+        assert!(parse("ID=ubuntu\nPRETTY_NAME=\"Ubuntu 24.10 (development)\"").is_prerelease());
+        assert!(!parse("ID=testing\nNAME=\"Testing Ground OS\"\nPRETTY_NAME=\"Testing Ground OS\"").is_prerelease());
+
+        assert!(parse("ID=ubuntu\nBUILD_ID=24.04-rc2").is_prerelease());
+        assert!(!parse(UBUNTU).is_prerelease());
+        assert!(!parse(FEDORA).is_prerelease());
+        assert!(!parse(EXAMPLE).is_prerelease());
+    }
+
+    #[test]
+    fn from_property_map() {
+        let mut props = BTreeMap::new();
+        props.insert("OperatingSystemPrettyName".to_owned(), "Fedora Linux 39 (Workstation Edition)".to_owned());
+        props.insert("OperatingSystemHomeURL".to_owned(), "https://fedoraproject.org/".to_owned());
+        props.insert("OperatingSystemCPEName".to_owned(), "cpe:/o:fedoraproject:fedora:39".to_owned());
+        props.insert("Hostname".to_owned(), "workstation".to_owned());
+
+        let os_release = OsRelease::from_property_map(&props);
+        assert_eq!(os_release.pretty_name, "Fedora Linux 39 (Workstation Edition)");
+        assert_eq!(os_release.home_url, "https://fedoraproject.org/");
+        assert_eq!(os_release.extra["CPE_NAME"], "cpe:/o:fedoraproject:fedora:39");
+        assert_eq!(os_release.extra.len(), 1);
+
+        props.insert("OperatingSystemReleaseData".to_owned(), FEDORA.to_owned());
+        let os_release = OsRelease::from_property_map(&props);
+        assert_eq!(os_release.id, "fedora");
+        assert_eq!(os_release.version_id, "39");
+    }
+
+    #[test]
+    fn quoting_report() {
+        let report = OsRelease::quoting_report("NAME=\"Arch Linux\"\n# COMMENT=\"x\"\nID=arch\nPRETTY_NAME=Arch Linux");
+        assert_eq!(report, vec![
+            ("NAME".to_owned(), true),
+            ("ID".to_owned(), false),
+            ("PRETTY_NAME".to_owned(), false),
+        ]);
+    }
+
+    #[cfg(feature = "ini")]
+    #[test]
+    fn to_ini() {
+        let ini = parse(EXAMPLE).to_ini();
+        assert!(ini.lines().any(|line| line == "[os-release]"));
+        assert!(ini.lines().any(|line| line == "[extra]"));
+        assert!(ini.lines().any(|line| line == "name=Arch Linux"));
+
+        let parsed = ini::Ini::load_from_str(&ini).unwrap();
+        assert_eq!(parsed.get_from(Some("os-release"), "ansi_color"), Some("38;2;23;147;209"));
+        assert_eq!(parsed.get_from(Some("extra"), "EXTRA_KEY"), Some("thing"));
+    }
+
+    #[test]
+    fn edit_field_in_file() {
+        let content = "# Comment\nNAME='Arch Linux'\r\nPRETTY_NAME=\"Arch Linux\"\n  ID=arch\nEXTRA_KEY=thing";
+        let path = temp_file("edit", content);
+
+        OsRelease::edit_field_in_file(&path, "PRETTY_NAME", "My \"Arch\"").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# Comment\nNAME='Arch Linux'\r\nPRETTY_NAME=\"My \\\"Arch\\\"\"\n  ID=arch\nEXTRA_KEY=thing"
+        );
+
+        OsRelease::edit_field_in_file(&path, "ID", "archarm").unwrap();
+        OsRelease::edit_field_in_file(&path, "VERSION_ID", "1").unwrap();
+        let edited = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            edited,
+            "# Comment\nNAME='Arch Linux'\r\nPRETTY_NAME=\"My \\\"Arch\\\"\"\n  ID=archarm\nEXTRA_KEY=thing\nVERSION_ID=\"1\"\n"
+        );
+
+        let path = temp_file("edit-export", "# ID=commented\n  export ID=arch\nexport  NAME=\"Arch\"\n");
+        OsRelease::edit_field_in_file(&path, "ID", "archarm").unwrap();
+        OsRelease::edit_field_in_file(&path, "NAME", "Arch Linux ARM").unwrap();
+        let edited = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(edited, "# ID=commented\n  export ID=archarm\nexport  NAME=\"Arch Linux ARM\"\n");
+    }
+
+    #[test]
+    fn to_lines() {
+        let os_release = parse(EXAMPLE);
+        let lines = os_release.to_lines();
+        assert!(lines.contains(&r#"NAME="Arch Linux""#.to_owned()));
+        assert!(lines.contains(&r#"EXTRA_KEY="thing""#.to_owned()));
+        assert_eq!(lines.len(), 11);
+        assert_eq!(os_release.to_string(), lines.join("\n") + "\n");
+        assert_eq!(parse(&os_release.to_string()), os_release);
+    }
+
+    #[test]
+    fn is_steamos() {
+        let steamos = parse(r#"NAME="SteamOS"
+PRETTY_NAME="SteamOS"
+VERSION_CODENAME=holo
+ID=steamos
+ID_LIKE=arch
+ANSI_COLOR="1;35"
+HOME_URL="https://www.steampowered.com/"
+VARIANT_ID=steamdeck
+VERSION_ID=3.5.7"#);
+        assert!(steamos.is_steamos());
+        assert!(parse("ID=holoiso\nID_LIKE=arch\nVARIANT_ID=steamdeck").is_steamos());
+        assert!(!parse(EXAMPLE).is_steamos());
+        assert!(!parse("ID=manjaro\nID_LIKE=arch").is_steamos());
+    }
+
+    #[test]
+    fn invalid_keys() {
+        let os_release = parse("ID=arch\n1FOO=bar\nfoo=baz\nVALID_KEY_2=ok");
+        assert_eq!(os_release.invalid_keys(), vec!["1FOO", "foo"]);
+        assert!(parse(EXAMPLE).invalid_keys().is_empty());
+    }
+
+    #[test]
+    fn write_with_options() {
+        let os_release = parse("NAME=\"Ubuntu\"\nVERSION=\"22.04.3 LTS (Jammy Jellyfish)\"\nID=ubuntu");
+
+        let mut output = Vec::new();
+        os_release.write_with_options(&mut output, &WriteOptions::default()).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), os_release.to_string());
+
+        let mut output = Vec::new();
+        let options = WriteOptions { synthesize_pretty_name: true };
+        os_release.write_with_options(&mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.lines().any(|line| line == r#"PRETTY_NAME="Ubuntu 22.04.3 LTS (Jammy Jellyfish)""#));
+        assert!(os_release.pretty_name.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn new_from_zst() {
+        let compressed = zstd::encode_all(EXAMPLE.as_bytes(), 0).unwrap();
+        let path = temp_file("zst", "");
+        std::fs::write(&path, compressed).unwrap();
+        let os_release = OsRelease::new_from_zst(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(os_release.unwrap(), parse(EXAMPLE));
+    }
+
+    #[test]
+    fn extra_diff() {
+        let old = parse("ID=arch\nKEPT=same\nOLD_KEY=gone\nCHANNEL=stable");
+        let new = parse("ID=manjaro\nKEPT=same\nNEW_KEY=here\nCHANNEL=beta");
+        assert_eq!(old.extra_diff(&new), ExtraDiff {
+            added:   vec![("NEW_KEY".to_owned(), "here".to_owned())],
+            removed: vec![("OLD_KEY".to_owned(), "gone".to_owned())],
+            changed: vec![("CHANNEL".to_owned(), "stable".to_owned(), "beta".to_owned())],
+        });
+        assert_eq!(old.extra_diff(&old), ExtraDiff::default());
+    }
+
+    #[test]
+    fn lossy_fields() {
+        assert!(parse(EXAMPLE).lossy_fields().is_empty());
+
+        let mut os_release = parse(EXAMPLE);
+        os_release.extra.insert("MY KEY".to_owned(), "value".to_owned());
+        os_release.pretty_name = "Arch\nLinux".to_owned();
+        assert_eq!(os_release.lossy_fields(), vec!["PRETTY_NAME", "MY KEY"]);
+    }
+
+    #[test]
+    fn derive_from() {
+        let base = parse(EXAMPLE);
+        let derived = OsRelease::derive_from(&base).id("myarch").name("My Arch").build();
+        assert_eq!(derived.id, "myarch");
+        assert_eq!(derived.id_like, "arch");
+        assert_eq!(derived.home_url, base.home_url);
+        assert_eq!(derived.bug_report_url, base.bug_report_url);
+        assert!(derived.pretty_name.is_empty());
+
+        let derived = OsRelease::derive_from(&parse(UBUNTU)).build();
+        assert_eq!(derived.id_like, "debian ubuntu");
+    }
+
+    #[cfg(all(feature = "tar", feature = "gzip"))]
+    fn tar_layer(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "oci")]
+    fn from_oci_layout() {
+        use std::io::Write;
+
+        let layout = std::env::temp_dir().join(format!("os-release-rs-oci-{}", std::process::id()));
+        let blobs = layout.join("blobs").join("sha256");
+        std::fs::create_dir_all(&blobs).unwrap();
+        let blob = |name: &str, content: &[u8]| std::fs::write(blobs.join(name), content).unwrap();
+
+        blob("base", &tar_layer(&[("etc/os-release", ROCKY), ("usr/lib/os-release", ROCKY)]));
+        blob("top", &tar_layer(&[("./etc/os-release", EXAMPLE)]));
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&tar_layer(&[("usr/lib/os-release", UBUNTU)])).unwrap();
+        blob("arm64", &gzip.finish().unwrap());
+
+        let tar = "application/vnd.oci.image.layer.v1.tar";
+        blob("manifest", format!(
+            r#"{{"layers": [{{"mediaType": "{0}", "digest": "sha256:base"}}, {{"mediaType": "{0}", "digest": "sha256:top"}}]}}"#,
+            tar,
+        ).as_bytes());
+        blob("manifest-arm64", format!(r#"{{"layers": [{{"mediaType": "{}+gzip", "digest": "sha256:arm64"}}]}}"#, tar).as_bytes());
+        blob("index", br#"{"manifests": [
+            {"digest": "sha256:manifest", "platform": {"os": "linux", "architecture": "amd64"}},
+            {"digest": "sha256:manifest-arm64", "platform": {"os": "linux", "architecture": "arm64", "variant": "v8"}}
+        ]}"#);
+        std::fs::write(layout.join("index.json"), r#"{"manifests": [
+            {"digest": "sha256:manifest", "annotations": {"org.opencontainers.image.ref.name": "arch"}},
+            {"digest": "sha256:index", "annotations": {"org.opencontainers.image.ref.name": "multi"}}
+        ]}"#).unwrap();
+
+        let arch = OsRelease::from_oci_layout(&layout, "arch", None);
+        let by_digest = OsRelease::from_oci_layout(&layout, "sha256:manifest", None);
+        let arm64 = OsRelease::from_oci_layout(&layout, "multi", Some("linux/arm64/v8"));
+        let no_platform = OsRelease::from_oci_layout(&layout, "multi", None);
+        let missing = OsRelease::from_oci_layout(&layout, "missing", None);
+        let _ = std::fs::remove_dir_all(&layout);
+
+        assert_eq!(arch.unwrap(), Some(parse(EXAMPLE)));
+        assert_eq!(by_digest.unwrap(), Some(parse(EXAMPLE)));
+        assert_eq!(arm64.unwrap(), Some(parse(UBUNTU)));
+        assert_eq!(no_platform.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn package_format() {
+        assert_eq!(parse(UBUNTU).package_format(), Some("deb"));
+        assert_eq!(parse(FEDORA).package_format(), Some("rpm"));
+        assert_eq!(parse(ROCKY).package_format(), Some("rpm"));
+        assert_eq!(parse(EXAMPLE).package_format(), Some("pkg.tar"));
+        assert_eq!(parse("ID=manjaro\nID_LIKE=arch").package_format(), Some("pkg.tar"));
+        assert_eq!(parse("ID=unknown").package_format(), None);
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(parse(EXAMPLE).summary(), "arch rolling (Arch Linux)");
+        assert_eq!(parse(UBUNTU).summary(), "ubuntu 22.04 (Ubuntu 22.04.3 LTS)");
+        assert_eq!(parse("ID=debian\nVERSION_ID=12").summary(), "debian 12");
+    }
+
+    #[test]
+    fn partition_extras() {
+        let os_release = parse("ID=arch\nFUTURE_KEY=soon\nx-custom=mine\nVendor_Key=too");
+        let (reserved_unknown, vendor) = os_release.partition_extras();
+        assert_eq!(reserved_unknown, BTreeMap::from([("FUTURE_KEY", "soon")]));
+        assert_eq!(vendor, BTreeMap::from([("Vendor_Key", "too"), ("x-custom", "mine")]));
+    }
+
+    #[test]
+    fn parse_numeric_escapes() {
+        let content = r#"NAME="\x41rch \0114inux"
+PRETTY_NAME="\xZZ and \q"
+VERSION='\x41'
+VERSION_ID="\\x41\\\x41"
+VERSION_CODENAME="\"\$\`\\""#;
+        let options = ParseOptions { numeric_escapes: true, ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(content, &options).unwrap();
+        assert_eq!(os_release.name, "Arch Linux");
+        assert_eq!(os_release.pretty_name, r"\xZZ and \q");
+        assert_eq!(os_release.version, r"\x41");
+        assert_eq!(os_release.version_id, r"\x41\A");
+        assert_eq!(os_release.version_codename, r#""$`\"#);
+        assert_eq!(OsReleaseParser::builder().numeric_escapes(true).build().parse_str(content).unwrap(), os_release);
+
+        let os_release = parse(content);
+        assert_eq!(os_release.name, r"\x41rch \0114inux");
+        assert_eq!(os_release.version_id, r"\x41\\x41");
+        assert_eq!(os_release.version_codename, r#""$`\"#);
+    }
+
+    #[test]
+    fn is_wsl() {
+        assert!(parse(&format!("{}\nWSL_DISTRIBUTION=Ubuntu", UBUNTU)).is_wsl());
+        assert!(parse("ID=ubuntu\nVARIANT_ID=wsl").is_wsl());
+        assert!(parse("ID=pengwin\nID_LIKE=debian").is_wsl());
+        assert!(!parse(UBUNTU).is_wsl());
+        assert!(!parse(EXAMPLE).is_wsl());
+    }
+
+    #[test]
+    #[cfg(all(feature = "tar", feature = "gzip"))]
+    fn from_tar_gz() {
+        use std::io::Write;
+
+        let gzip = |content: &[u8]| {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(content).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        let layer = gzip(&tar_layer(&[("usr/lib/os-release", ROCKY), ("etc/os-release", EXAMPLE)]));
+        assert_eq!(OsRelease::from_tar_gz(layer.as_slice()).unwrap(), parse(EXAMPLE));
+
+        let layer = gzip(&tar_layer(&[("usr/lib/os-release", ROCKY)]));
+        assert_eq!(OsRelease::from_tar_gz(layer.as_slice()).unwrap(), parse(ROCKY));
+
+        let tar = tar_layer(&[("etc/os-release", EXAMPLE)]);
+        assert!(matches!(OsRelease::from_tar_gz(tar.as_slice()), Err(OsReleaseError::NotGzip)));
+        let text = gzip(EXAMPLE.as_bytes());
+        assert!(matches!(OsRelease::from_tar_gz(text.as_slice()), Err(OsReleaseError::NotTar(_))));
+        let layer = gzip(&tar_layer(&[("etc/hostname", "arch")]));
+        assert!(matches!(OsRelease::from_tar_gz(layer.as_slice()), Err(OsReleaseError::NoOsRelease)));
+    }
+
+    #[test]
+    fn os_release_parser() {
+        let parser = OsReleaseParser::builder()
+            .mode(ParseMode::Strict)
+            .comment_chars(vec!['#', ';'])
+            .build();
+        assert_eq!(parser.options().mode, ParseMode::Strict);
+
+        let os_release = parser.parse_str("; comment\nID=arch\nNAME=\"Arch Linux\"").unwrap();
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.name, "Arch Linux");
+
+        let path = temp_file("parser", UBUNTU);
+        let os_release = parser.parse_file(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(os_release.unwrap(), parse(UBUNTU));
+
+        assert_eq!(parser.parse_reader(EXAMPLE.as_bytes()).unwrap(), parse(EXAMPLE));
+        assert!(matches!(parser.parse_reader(&b"NAME=\"Caf\xe9\""[..]), Err(OsReleaseError::InvalidUtf8(_))));
+        assert!(matches!(parser.parse_str("NAME=\"Arch"), Err(OsReleaseError::UnterminatedQuote { .. })));
+    }
+
+    #[test]
+    fn parse_filtered() {
+        let os_release = OsRelease::parse_filtered(UBUNTU, |key| key.starts_with("VERSION"));
+        assert_eq!(os_release, OsRelease {
+            version: "22.04.3 LTS (Jammy Jellyfish)".to_owned(),
+            version_id: "22.04".to_owned(),
+            version_codename: "jammy".to_owned(),
+            ..OsRelease::default()
+        });
+
+        let os_release = OsRelease::parse_filtered("ID = arch\nexport VERSION_ID=1\n  NAME=Arch", |key| key == "ID" || key == "VERSION_ID");
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.version_id, "1");
+        assert!(os_release.name.is_empty());
+    }
+
+    #[test]
+    fn id_name_consistent() {
+        assert!(parse(EXAMPLE).id_name_consistent());
+        assert!(parse(UBUNTU).id_name_consistent());
+        assert!(parse("ID=rhel\nNAME=\"Red Hat Enterprise Linux\"\nPRETTY_NAME=\"Red Hat Enterprise Linux 9.3 (Plow)\"").id_name_consistent());
+        assert!(parse("ID=arch").id_name_consistent());
+
+        assert!(!parse("ID=arch\nPRETTY_NAME=\"Ubuntu 22.04\"").id_name_consistent());
+        assert!(!parse("ID=arch\nNAME=\"Arch Linux\"\nPRETTY_NAME=\"Ubuntu Linux\"").id_name_consistent());
+    }
+
+    #[test]
+    fn brand_conflict() {
+        let os_release = parse("ID=debian\nNAME=\"Debian GNU/Linux\"\nPRETTY_NAME=\"Ubuntu 22.04\"");
+        assert_eq!(os_release.brand_conflict(), Some(("debian".to_owned(), "Ubuntu 22.04".to_owned())));
+
+        assert_eq!(parse(UBUNTU).brand_conflict(), None);
+        assert_eq!(parse("ID=opensuse-leap\nPRETTY_NAME=\"openSUSE Leap 15.5\"").brand_conflict(), None);
+        assert_eq!(parse("ID=rhel\nNAME=\"Red Hat Enterprise Linux\"\nPRETTY_NAME=\"Red Hat Enterprise Linux 9.3\"").brand_conflict(), None);
+        assert_eq!(parse("ID=debian").brand_conflict(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn into_json_value() {
+        let mut value = serde_json::Value::from(&parse(&format!("{}\nFOO=bar", EXAMPLE)));
+        assert_eq!(value["NAME"], "Arch Linux");
+        assert_eq!(value["FOO"], "bar");
+        assert!(value.get("VERSION_ID").is_none());
+
+        value["NAME"] = "My Arch".into();
+        let os_release = OsRelease::from_metadata_json(&value.to_string(), "").unwrap();
+        assert_eq!(os_release.name, "My Arch");
+
+        let options = ParseOptions { include_standard_in_extra: true, ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(EXAMPLE, &options).unwrap();
+        assert_eq!(serde_json::Value::from(&os_release), serde_json::Value::from(&parse(EXAMPLE)));
+    }
+
+    #[test]
+    fn system_identity_at() {
+        let os_release = temp_file("identity-os-release", EXAMPLE);
+        let machine_id = temp_file("identity-machine-id", "0123456789abcdef0123456789abcdef\n");
+        let missing = std::env::temp_dir().join("os-release-rs-identity-missing");
+
+        let identity = OsRelease::system_identity_at(&[&os_release], &machine_id);
+        let without_machine_id = OsRelease::system_identity_at(&[&os_release], &missing);
+        let _ = std::fs::remove_file(&os_release);
+        let _ = std::fs::remove_file(&machine_id);
+
+        let identity = identity.unwrap();
+        assert_eq!(identity.os_release, parse(EXAMPLE));
+        assert_eq!(identity.machine_id, "0123456789abcdef0123456789abcdef");
+        assert_eq!(without_machine_id.unwrap().machine_id, "");
+    }
+
+    #[test]
+    fn new_flatpak_host_at() {
+        let host = std::env::temp_dir().join(format!("os-release-rs-flatpak-{}", std::process::id()));
+        std::fs::create_dir_all(&host).unwrap();
+        let sandbox = temp_file("flatpak-sandbox", "ID=org.freedesktop.platform\nNAME=\"Freedesktop SDK\"");
+
+        let without_host = OsRelease::new_flatpak_host_at(&host, &[&sandbox]);
+        std::fs::write(host.join("os-release"), EXAMPLE).unwrap();
+        let with_host = OsRelease::new_flatpak_host_at(&host, &[&sandbox]);
+        let _ = std::fs::remove_dir_all(&host);
+        let _ = std::fs::remove_file(&sandbox);
+
+        assert_eq!(without_host.unwrap().id, "org.freedesktop.platform");
+        assert_eq!(with_host.unwrap(), parse(EXAMPLE));
+    }
+
+    #[test]
+    fn package_manager() {
+        assert_eq!(parse("ID=debian\nVERSION_ID=12").package_manager(), Some("apt"));
+        assert_eq!(parse(UBUNTU).family(), Some("debian"));
+        assert_eq!(parse(UBUNTU).package_manager(), Some("apt"));
+        assert_eq!(parse(FEDORA).package_manager(), Some("dnf"));
+        assert_eq!(parse(ROCKY).package_manager(), Some("dnf"));
+        assert_eq!(parse(EXAMPLE).package_manager(), Some("pacman"));
+        assert_eq!(parse("ID=unknown").package_manager(), None);
+    }
+
+    #[test]
+    fn from_pairs() {
+        let pairs = vec![
+            ("NAME".to_owned(), "\"Arch Linux\"".to_owned()),
+            ("ID".to_owned(), "arch".to_owned()),
+            ("LOGO".to_owned(), "'archlinux-logo'".to_owned()),
+            ("FOO".to_owned(), "bar".to_owned()),
+            ("VERSION".to_owned(), r#""\"rolling\"""#.to_owned()),
+        ];
+        let os_release = OsRelease::from_pairs(pairs);
+        assert_eq!(os_release.version, "\"rolling\"");
+        assert_eq!(os_release.name, "Arch Linux");
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.logo, "archlinux-logo");
+        assert_eq!(os_release.extra, BTreeMap::from([("FOO".to_owned(), "bar".to_owned())]));
+    }
+
+    #[test]
+    fn parse_indented_keys() {
+        let content = "\t  ID=arch\n  \tNAME=\"Arch Linux\"\n\t\tFOO=bar\n  # indented comment";
+        let os_release = parse(content);
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.name, "Arch Linux");
+        assert_eq!(os_release.extra, BTreeMap::from([("FOO".to_owned(), "bar".to_owned())]));
+        assert_eq!(OsRelease::parse_with_options(content, &ParseOptions::default()).unwrap(), os_release);
+    }
+
+    #[test]
+    fn release_model() {
+        assert_eq!(parse(EXAMPLE).release_model(), ReleaseModel::Rolling);
+        assert_eq!(parse("ID=gentoo\nVERSION_ID=2.15").release_model(), ReleaseModel::Rolling);
+        assert_eq!(parse("ID=debian\nVERSION_ID=\"12\"").release_model(), ReleaseModel::Pointed);
+        assert_eq!(parse(ROCKY).release_model(), ReleaseModel::Pointed);
+        assert_eq!(parse(UBUNTU).release_model(), ReleaseModel::DateBased);
+        assert_eq!(parse("ID=nixos\nVERSION_ID=\"23.11\"").release_model(), ReleaseModel::DateBased);
+        assert_eq!(parse("ID=unknown").release_model(), ReleaseModel::Unknown);
+
+        assert_eq!(parse(UBUNTU).release_year(), Some(2022));
+        assert_eq!(parse("VERSION_ID=2023.1").release_year(), Some(2023));
+        assert_eq!(parse("VERSION_ID=15.5").release_year(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn is_end_of_life() {
+        assert_eq!(parse("ID=fedora\nSUPPORT_END=2000-01-01").is_end_of_life(), Some(true));
+        assert_eq!(parse("ID=fedora\nSUPPORT_END=2999-12-31").is_end_of_life(), Some(false));
+        assert_eq!(parse("ID=fedora").is_end_of_life(), None);
+        assert_eq!(parse("ID=fedora\nSUPPORT_END=2024-02-30").is_end_of_life(), None);
+    }
+
+    #[test]
+    fn url_extras() {
+        let os_release = parse(&format!("{}\nVENDOR_URL=\"https://example.com/\"\nVENDOR_NAME=Example", EXAMPLE));
+        assert_eq!(os_release.url_extras(), BTreeMap::from([("VENDOR_URL", "https://example.com/")]));
+    }
+
+    #[test]
+    fn field_map() {
+        let map = parse(EXAMPLE).field_map();
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![
+            OsReleaseField::AnsiColor,
+            OsReleaseField::BuildId,
+            OsReleaseField::BugReportUrl,
+            OsReleaseField::DocumentationUrl,
+            OsReleaseField::HomeUrl,
+            OsReleaseField::Id,
+            OsReleaseField::Logo,
+            OsReleaseField::Name,
+            OsReleaseField::PrettyName,
+            OsReleaseField::SupportUrl,
+        ]);
+        assert_eq!(map[&OsReleaseField::Name], "Arch Linux");
+    }
+
+    #[test]
+    fn parse_strict_bom() {
+        let content = "\u{feff}NAME=\"Arch Linux\"\nID=arch";
+        let (os_release, _) = OsRelease::parse_with_mode(content, ParseMode::Lenient).unwrap();
+        assert_eq!(os_release.name, "Arch Linux");
+        assert!(matches!(OsRelease::parse_with_mode(content, ParseMode::Strict), Err(OsReleaseError::UnexpectedBom)));
+        assert!(OsRelease::parse_with_mode("NAME=\"Arch \u{feff}Linux\"", ParseMode::Strict).is_ok());
+    }
+
+    #[test]
+    fn transform_lines() {
+        let content = "# comment\nNAME=\"Arch \\\"Linux\\\"\"\nID=arch\nLOGO=archlinux-logo";
+        let output = OsRelease::transform_lines(content.lines(), |key, value| match key {
+            "LOGO" => None,
+            _ => Some((key.to_owned(), value.to_uppercase())),
+        });
+        assert_eq!(output, "NAME=\"ARCH \\\"LINUX\\\"\"\nID=\"ARCH\"\n");
+    }
+
+    #[test]
+    fn guessed_edition() {
+        assert_eq!(parse("ID=fedora\nPRETTY_NAME=\"Fedora Linux 39 (Server Edition)\"").guessed_edition(), Some("server"));
+        assert_eq!(parse("ID=fedora\nVARIANT=\"Fedora Server\"").guessed_edition(), Some("server"));
+        assert_eq!(parse(FEDORA).guessed_edition(), Some("desktop"));
+        assert_eq!(parse("ID=fedora\nVARIANT_ID=coreos").guessed_edition(), Some("minimal"));
+        assert_eq!(parse(EXAMPLE).guessed_edition(), None);
+    }
+
+    #[test]
+    fn suse_info() {
+        let sles = r#"NAME="SLES"
+VERSION="15-SP5"
+VERSION_ID="15.5"
+PRETTY_NAME="SUSE Linux Enterprise Server 15 SP5"
+ID="sles"
+ID_LIKE="suse"
+CPE_NAME="cpe:/o:suse:sles:15:sp5""#;
+        assert_eq!(parse(sles).suse_info(), Some(SuseInfo {
+            product:      "sles".to_owned(),
+            major:        Some(15),
+            service_pack: Some(5),
+        }));
+        assert_eq!(parse(sles).extra["CPE_NAME"], "cpe:/o:suse:sles:15:sp5");
+
+        let sled = "ID=sled\nID_LIKE=suse\nVERSION_ID=12\nSPVERSION=4";
+        assert_eq!(parse(sled).suse_info().unwrap().service_pack, Some(4));
+        assert_eq!(parse(sled).suse_info().unwrap().product, "sled");
+
+        assert_eq!(parse(FEDORA).suse_info(), None);
+    }
+
+    #[test]
+    fn sorted_pairs() {
+        let pairs = parse(EXAMPLE).sorted_pairs();
+        assert!(pairs.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(pairs.len(), 11);
+        assert!(pairs.contains(&("NAME".to_owned(), "Arch Linux".to_owned())));
+        assert!(pairs.contains(&("EXTRA_KEY".to_owned(), "thing".to_owned())));
+    }
+
+    #[test]
+    fn described_fields() {
+        let fields = parse(EXAMPLE).described_fields();
+        assert_eq!(fields.len(), OsReleaseField::ALL.len());
+        assert!(fields.iter().all(|(_, description, _)| !description.is_empty()));
+
+        let (_, description, value) = fields.iter().find(|(field, _, _)| *field == OsReleaseField::Name).unwrap();
+        assert_eq!(*description, "The name of the operating system.");
+        assert_eq!(value, "Arch Linux");
+        assert!(fields.iter().any(|(field, _, value)| *field == OsReleaseField::VersionId && value.is_empty()));
+    }
+
+    #[test]
+    fn parse_quoted_whitespace() {
+        for content in &["NAME=\" Arch \"", "NAME = \" Arch \"", "  NAME =\t' Arch '  "] {
+            assert_eq!(parse(content).name, " Arch ");
+            assert!(parse(content).extra.is_empty());
+            assert_eq!(OsRelease::parse_with_options(content, &ParseOptions::default()).unwrap().name, " Arch ");
+        }
+        assert_eq!(parse("FOO = \" bar \"").extra["FOO"], " bar ");
+        assert_eq!(parse("ID = arch ").id, "arch");
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn new_from_auto() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(EXAMPLE.as_bytes()).unwrap();
+        let plain = temp_file("auto-plain", EXAMPLE);
+        let gzipped = temp_file("auto-gzipped", "");
+        std::fs::write(&gzipped, encoder.finish().unwrap()).unwrap();
+
+        let from_plain = OsRelease::new_from_auto(&plain);
+        let from_gzipped = OsRelease::new_from_auto(&gzipped);
+        let _ = std::fs::remove_file(&plain);
+        let _ = std::fs::remove_file(&gzipped);
+
+        assert_eq!(from_plain.unwrap(), parse(EXAMPLE));
+        assert_eq!(from_gzipped.unwrap(), parse(EXAMPLE));
+    }
+
+    #[test]
+    fn missing_recommended() {
+        assert_eq!(parse(EXAMPLE).missing_recommended(), vec!["PRIVACY_POLICY_URL"]);
+        assert_eq!(parse("ID=arch").missing_recommended(), vec!["HOME_URL", "SUPPORT_URL", "BUG_REPORT_URL", "PRIVACY_POLICY_URL"]);
+    }
+
+    #[test]
+    fn fill_defaults() {
+        let mut os_release = parse("ID=arch\nNAME=\"My Arch\"\nFOO=mine");
+        os_release.fill_defaults(&parse("NAME=\"Arch Linux\"\nVERSION=rolling\nFOO=default\nBAR=default"));
+        assert_eq!(os_release.name, "My Arch");
+        assert_eq!(os_release.version, "rolling");
+        assert_eq!(os_release.extra["FOO"], "mine");
+        assert_eq!(os_release.extra["BAR"], "default");
+    }
+
+    #[test]
+    fn parse_raw_values() {
+        let content = "NAME = \"Arch Linux\"\nID=arch\nLOGO='archlinux-logo'\nVERSION=\"\\x41\"";
+        let options = ParseOptions { raw_values: true, ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(content, &options).unwrap();
+        assert_eq!(os_release.name, "\"Arch Linux\"");
+        assert_eq!(os_release.id, "arch");
+        assert_eq!(os_release.logo, "'archlinux-logo'");
+        assert_eq!(os_release.version, "\"\\x41\"");
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn json_schema() {
+        let schema = OsRelease::json_schema();
+        let properties = schema["properties"].as_object().unwrap();
+        assert_eq!(properties["NAME"]["type"], "string");
+        assert_eq!(schema["additionalProperties"]["type"], "string");
+
+        let keys: Vec<&str> = properties.keys().map(String::as_str).collect();
+        let mut expected: Vec<&str> = parse("").fields().iter().map(|(key, _)| *key).collect();
+        expected.sort_unstable();
+        assert_eq!(keys, expected);
+
+        let value = serde_json::Value::from(&parse(EXAMPLE));
+        assert!(value.as_object().unwrap().values().all(serde_json::Value::is_string));
+        assert!(schema.get("required").is_none());
+    }
+
+    #[test]
+    fn architecture_mismatch() {
+        let actual = current_architecture();
+        assert_eq!(parse(&format!("ID=arch\nARCHITECTURE={}", actual)).architecture_mismatch(), None);
+        assert_eq!(parse("ID=arch").architecture_mismatch(), None);
+        if actual == "x86-64" {
+            assert_eq!(parse("ID=arch\nARCHITECTURE=amd64").architecture_mismatch(), None);
+        }
+
+        let other = if actual == "s390x" { "arm64" } else { "s390x" };
+        assert_eq!(
+            parse(&format!("ID=arch\nARCHITECTURE={}", other)).architecture_mismatch(),
+            Some((other.to_owned(), actual.to_owned()))
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn source_info_at() {
+        let root = std::env::temp_dir().join(format!("os-release-rs-source-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("etc")).unwrap();
+        std::fs::create_dir_all(root.join("usr/lib")).unwrap();
+        std::fs::write(root.join("usr/lib/os-release"), EXAMPLE).unwrap();
+        std::os::unix::fs::symlink("../usr/lib/os-release", root.join("etc/os-release")).unwrap();
+
+        let link = OsRelease::source_info_at(root.join("etc/os-release"));
+        let file = OsRelease::source_info_at(root.join("usr/lib/os-release"));
+        let resolved = std::fs::canonicalize(root.join("usr/lib/os-release")).unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(link.unwrap(), SourceInfo {
+            resolved_path: resolved.clone(),
+            is_symlink:    true,
+            target:        Some("../usr/lib/os-release".into()),
+        });
+        assert_eq!(file.unwrap(), SourceInfo { resolved_path: resolved, is_symlink: false, target: None });
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn new_from_guarded() {
+        let root = std::env::temp_dir().join(format!("os-release-rs-guarded-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::os::unix::fs::symlink("b", root.join("a")).unwrap();
+        std::os::unix::fs::symlink("a", root.join("b")).unwrap();
+        let dir = root.file_name().unwrap().to_string_lossy().into_owned();
+        std::os::unix::fs::symlink(format!("../{}/growing", dir), root.join("growing")).unwrap();
+        std::os::unix::fs::symlink("missing", root.join("broken")).unwrap();
+        std::fs::write(root.join("file"), EXAMPLE).unwrap();
+        std::os::unix::fs::symlink("file", root.join("link")).unwrap();
+
+        let looped = OsRelease::new_from_guarded(root.join("a"));
+        let growing = OsRelease::new_from_guarded(root.join("growing"));
+        let broken = OsRelease::new_from_guarded(root.join("broken"));
+        let linked = OsRelease::new_from_guarded(root.join("link"));
+        let missing = OsRelease::new_from_guarded(root.join("missing"));
+        let _ = std::fs::remove_dir_all(&root);
+
+        match looped {
+            Err(OsReleaseError::SymlinkLoop { path, target }) => {
+                assert_eq!(path, root.join("a"));
+                assert_eq!(target, Path::new("b"));
+            }
+            other => panic!("expected SymlinkLoop, got {:?}", other),
+        }
+        assert!(matches!(growing, Err(OsReleaseError::SymlinkLoop { .. })));
+        assert!(matches!(broken, Err(OsReleaseError::BrokenSymlink { .. })));
+        assert_eq!(linked.unwrap(), parse(EXAMPLE));
+        assert!(matches!(missing, Err(OsReleaseError::Io(why)) if why.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn image_version_cmp() {
+        let old = parse("IMAGE_ID=cashier\nIMAGE_VERSION=2023.1");
+        let new = parse("IMAGE_ID=cashier\nIMAGE_VERSION=2023.2");
+        assert_eq!(old.image_version_cmp(&new), Some(Ordering::Less));
+        assert_eq!(new.image_version_cmp(&old), Some(Ordering::Greater));
+        assert_eq!(new.image_version_cmp(&parse("IMAGE_VERSION=2023.2.0")), Some(Ordering::Equal));
+        assert_eq!(new.image_version_cmp(&parse("IMAGE_ID=cashier")), None);
+        assert_eq!(new.image_version_cmp(&parse("IMAGE_VERSION=nightly")), None);
+    }
+
+    #[test]
+    fn compat_token() {
+        assert_eq!(parse(EXAMPLE).compat_token(), "arch");
+        assert_eq!(parse(UBUNTU).compat_token(), "ubuntu:22.04");
+        assert_eq!(parse("ID=alpine\nVERSION_ID=3.19.1").compat_token(), "alpine:3.19");
+        assert_eq!(parse("ID=debian\nVERSION_ID=12").compat_token(), "debian:12");
+        assert_eq!(parse("VERSION_ID=22.04").compat_token(), "");
+    }
+
+    #[test]
+    fn expand_variables_growth() {
+        let mut os_release = parse("NAME=Arch\nA=\"x${A}${A}\"\nB=\"${A}-${NAME}\"");
+        os_release.expand_variables_with_limit(64);
+        assert!(os_release.extra["A"].len() <= 4096);
+        assert!(os_release.extra["B"].len() <= 4096);
+        assert!(os_release.extra["B"].ends_with("-Arch"));
+    }
+
+    #[test]
+    fn edit_field_in_file_concurrent() {
+        let path = temp_file("edit-concurrent", EXAMPLE);
+        let threads: Vec<_> = (0..8).map(|i| {
+            let path = path.clone();
+            thread::spawn(move || OsRelease::edit_field_in_file(&path, "BUILD_ID", &i.to_string()))
+        }).collect();
+        for thread in threads {
+            thread.join().unwrap().unwrap();
+        }
+
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let leftovers = std::fs::read_dir(std::env::temp_dir()).unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&format!(".{}.", file_name)))
+            .count();
+        let os_release = OsRelease::new_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(leftovers, 0);
+        assert!(os_release.build_id.parse::<u32>().unwrap() < 8);
+
+        assert!(OsRelease::edit_field_in_file("/nonexistent/os-release", "ID", "arch").is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let mut os_release = parse(EXAMPLE);
+        os_release.pretty_name = r#"My "Arch" \ $HOME `uname`"#.to_owned();
+        os_release.extra.insert("QUOTED".to_owned(), r#"\x41 "b""#.to_owned());
+        assert_eq!(parse(&os_release.to_string()), os_release);
+        assert!(os_release.lossy_fields().is_empty());
+    }
+
+    #[test]
+    fn include_standard_in_extra_round_trip() {
+        let options = ParseOptions { include_standard_in_extra: true, ..ParseOptions::default() };
+        let os_release = OsRelease::parse_with_options(EXAMPLE, &options).unwrap();
+        let lines = os_release.to_lines();
+        assert_eq!(lines.iter().filter(|line| line.starts_with("NAME=")).count(), 1);
+        assert_eq!(os_release.to_export_script().matches("export NAME=").count(), 1);
+        assert_eq!(os_release.sorted_pairs(), parse(EXAMPLE).sorted_pairs());
+        assert_eq!(os_release.canonical_bytes(), parse(EXAMPLE).canonical_bytes());
+        assert!(os_release.lossy_fields().is_empty());
+
+        let reparsed = OsRelease::parse_with_options(&os_release.to_string(), &options).unwrap();
+        assert_eq!(reparsed, os_release);
     }
 }